@@ -1,11 +1,54 @@
 use iced::{
-    widget::{button, column, container, row, text, scrollable, Space, text_input, Button},
-    Alignment, Element, Length, Sandbox, Settings, Color, Theme,
+    widget::{button, canvas, checkbox, column, container, row, text, scrollable, Space, text_input, Button},
+    executor, Alignment, Application, Command, Element, Length, Settings, Subscription, Color, Theme, Point, Rectangle, Renderer, Size, mouse,
 };
-use serde::{Deserialize, Serialize};
-use chrono::Local;
+use serde::{Deserialize, Deserializer, Serialize};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
 use uuid::Uuid;
+use qrcode::QrCode;
+use image::Luma;
+
+const VENUE_NAME: &str = "Premium Theatre";
+
+// Repeated confirm clicks inside this window are treated as an accidental double-click
+const CONFIRM_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(1000);
+
+// Discount applied to every booking of a season pass covering two or more shows
+const SEASON_PASS_DISCOUNT: f64 = 0.15;
+
+// Kiosk reservations that aren't paid at the counter within this window are released
+const KIOSK_HOLD_MINUTES: i64 = 10;
+
+// Halls larger than this are booked by seat code instead of laying out one button per seat
+const MAX_GRID_RENDER_SEATS: usize = 500;
+
+// Hall size limits; rows are lettered A-Z, so there can be at most 26
+const MAX_HALL_ROWS: usize = 26;
+const MAX_HALL_COLS: usize = 50;
+
+// Tallest the seat grid may grow before it scrolls, so the booking form below stays on screen
+const SEAT_GRID_MAX_HEIGHT: f32 = 320.0;
+
+// How often bookings are written to bookings_export.json when they have changed
+const AUTO_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// Group discount tiers as (minimum seats in one booking, discount), largest first
+const GROUP_DISCOUNTS: [(usize, f64); 2] = [(6, 0.15), (4, 0.10)];
+
+// How many booking/cancellation actions can be undone
+const UNDO_LIMIT: usize = 10;
+
+// Occupancy (percent) at which operators get an early sell-out warning
+const CAPACITY_ALERT_PCT: f64 = 90.0;
+
+// Booking codes skip 0/O and 1/I so they can be read back over the counter without confusion
+const BOOKING_CODE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+const BOOKING_CODE_LEN: usize = 6;
 
 // ============================================================================
 // Data Models
@@ -19,7 +62,71 @@ struct Show {
     time: String,
     hall: String,
     price: f64,
+    // Derived from the hall capacity and bookings at startup, so not part of shows.json
+    #[serde(skip)]
     available_seats: usize,
+    #[serde(default)]
+    overbook_pct: f32,
+    #[serde(default)]
+    transpose_grid: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    // 1-based seat columns followed by an aisle
+    #[serde(default = "default_aisles")]
+    aisle_after: Vec<usize>,
+    #[serde(default = "default_rows")]
+    rows: usize,
+    #[serde(default = "default_cols")]
+    cols: usize,
+    #[serde(default)]
+    emoji: String,
+    // Surcharge for premium screenings, applied on top of seat-tier factors
+    #[serde(default = "default_price_multiplier")]
+    price_multiplier: f64,
+}
+
+fn default_price_multiplier() -> f64 {
+    1.0
+}
+
+fn default_rows() -> usize {
+    4
+}
+
+fn default_cols() -> usize {
+    5
+}
+
+fn default_aisles() -> Vec<usize> {
+    vec![2]
+}
+
+impl Show {
+    fn emoji(&self) -> &str {
+        if self.emoji.trim().is_empty() { "🎬" } else { &self.emoji }
+    }
+
+    // Standard-seat price with the show's multiplier applied; every price computation starts here
+    fn effective_price(&self) -> f64 {
+        self.price * self.price_multiplier
+    }
+
+    fn price_label(&self, price_format: &PriceFormat) -> String {
+        if self.price_multiplier == 1.0 {
+            price_format.format(self.effective_price())
+        } else {
+            format!("{} ({}x)", price_format.format(self.effective_price()), self.price_multiplier)
+        }
+    }
+
+    // Falls back to the stored string when the date can't be parsed
+    fn display_date(&self, format: DateFormat) -> String {
+        NaiveDate::parse_from_str(&self.date, "%d-%m-%Y").map_or_else(|_| self.date.clone(), |date| format.format(date))
+    }
+
+    fn starts_at(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&format!("{} {}", self.date, self.time), "%d-%m-%Y %H:%M").ok()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,9 +134,77 @@ struct Booking {
     id: String,
     show_id: usize,
     customer_name: String,
-    seat: String,
+    #[serde(alias = "seat", deserialize_with = "seat_codes")]
+    seats: Vec<String>,
+    #[serde(default)]
+    seat_tiers: Vec<SeatTier>,
     booking_time: String,
-    price: f64,
+    price: f64, // net amount charged
+    // Amount before group or season pass discounts; 0 on bookings made before it was recorded
+    #[serde(default)]
+    gross_price: f64,
+    #[serde(default)]
+    attended: bool,
+    #[serde(default)]
+    no_show: bool,
+    #[serde(default)]
+    overbooked: bool,
+    #[serde(default)]
+    pass_id: Option<String>,
+    #[serde(default)]
+    receipt_no: String,
+    #[serde(default)]
+    group_name: Option<String>,
+    #[serde(default)]
+    phone: Option<String>,
+    // Set while a kiosk reservation is waiting to be paid at the counter
+    #[serde(default)]
+    expires_at: Option<String>,
+    // Cash received and change given at the box office; None for non-cash sales
+    #[serde(default)]
+    tendered: Option<f64>,
+    #[serde(default)]
+    change: Option<f64>,
+    // Charged on top of the discounted price; grand_total is what the customer actually pays
+    #[serde(default)]
+    service_charge: f64,
+    #[serde(default)]
+    grand_total: f64,
+}
+
+impl Booking {
+    // Seat codes with their tier noted for anything above Standard, e.g. "A1 (VIP), C3"
+    fn seat_list(&self) -> String {
+        if self.seats.is_empty() {
+            return "Unassigned".to_string();
+        }
+        self.seats.iter().enumerate().map(|(i, code)| match self.seat_tiers.get(i) {
+            Some(tier) if *tier != SeatTier::Standard => format!("{} ({:?})", code, tier),
+            _ => code.clone(),
+        }).collect::<Vec<_>>().join(", ")
+    }
+
+    // Customer name tagged with the end of the booking id, e.g. "John Smith (#QX2A)", so
+    // customers who share a name can be told apart in lists
+    fn name_with_ref(&self) -> String {
+        let start = self.id.char_indices().rev().nth(3).map_or(0, |(i, _)| i);
+        format!("{} (#{})", self.customer_name, &self.id[start..])
+    }
+}
+
+// Older exports stored a single `seat` string; accept either shape
+fn seat_codes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(seat) if seat == "Unassigned" => Vec::new(),
+        OneOrMany::One(seat) => vec![seat],
+        OneOrMany::Many(seats) => seats,
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +213,87 @@ struct Seat {
     col: usize,
     is_booked: bool,
     booking_id: Option<String>,
+    #[serde(default)]
+    tier: SeatTier,
+    // Accessible/house seats held back from normal sale
+    #[serde(default)]
+    is_reserved: bool,
+    // Position in the overall sequence of seats sold (1 = first), for the popularity heat map
+    #[serde(default)]
+    booking_order: Option<u64>,
+}
+
+impl Seat {
+    fn is_bookable(&self) -> bool {
+        !self.is_booked && !self.is_reserved
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+enum SeatTier {
+    #[default]
+    Standard,
+    Premium,
+    VIP,
+}
+
+impl SeatTier {
+    // Row A is VIP and row B Premium; the rest of the hall is Standard
+    fn for_row(row: usize) -> Self {
+        match row {
+            0 => SeatTier::VIP,
+            1 => SeatTier::Premium,
+            _ => SeatTier::Standard,
+        }
+    }
+
+    fn factor(self) -> f64 {
+        match self {
+            SeatTier::Standard => 1.0,
+            SeatTier::Premium => 1.25,
+            SeatTier::VIP => 1.5,
+        }
+    }
+
+    fn border_color(self) -> Color {
+        match self {
+            SeatTier::Standard => Color::from_rgb(0.3, 0.3, 0.4),
+            SeatTier::Premium => Color::from_rgb(0.6, 0.4, 0.9),
+            SeatTier::VIP => Color::from_rgb(0.95, 0.75, 0.2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+struct SeatPalette {
+    available: [u8; 3],
+    selected: [u8; 3],
+    booked: [u8; 3],
+    colored_seats: bool,
+}
+
+impl Default for SeatPalette {
+    fn default() -> Self {
+        Self { available: [120, 177, 89], selected: [253, 203, 88], booked: [221, 46, 68], colored_seats: false }
+    }
+}
+
+impl SeatPalette {
+    fn color_for(&self, seat: &Seat, highlight: SeatHighlight) -> Color {
+        if seat.is_reserved { return Color::from_rgb8(90, 90, 90); }
+        if seat.is_booked && highlight == SeatHighlight::JustBooked { return Color::from_rgb8(150, 90, 220); }
+        let [r, g, b] = if seat.is_booked { self.booked } else if highlight == SeatHighlight::Selected { self.selected } else { self.available };
+        Color::from_rgb8(r, g, b)
+    }
+
+    // Operators can override the seat colors with a seat_palette.json next to the app
+    fn load() -> Self {
+        fs::read_to_string("seat_palette.json").ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
 }
 
 // ============================================================================
@@ -50,14 +306,207 @@ struct TheatreApp {
     bookings: Vec<Booking>,
     seats: Vec<Vec<Vec<Seat>>>, 
     selected_show: Option<usize>,
-    selected_seat: Option<(usize, usize)>,
+    selected_seats: HashMap<usize, Vec<(usize, usize)>>,
     customer_name: String,
     booking_id_input: String,
     error_message: Option<String>,
     success_message: Option<String>,
+    palette: SeatPalette,
+    prefs: Preferences,
+    max_seats_per_booking: usize,
+    // Seats taken by the last confirmed booking, highlighted until the next selection or view change
+    last_booked: Vec<(usize, usize)>,
+    theme: Theme,
+    stats_cache: StatsCache,
+    last_confirm_instant: Option<Instant>,
+    pass_shows: Vec<usize>,
+    pass_seat_input: String,
+    tag_filter: Option<String>,
+    show_upcoming_only: bool,
+    seat_code_input: String,
+    group_input: String,
+    phone_input: String,
+    tendered_input: String,
+    records_group_filter: Option<String>,
+    records_filter: String,
+    seat_lookup_input: String,
+    reset_confirm_input: String,
+    // Bookings JSON as last written to disk, so auto-save only writes when something changed
+    last_saved: String,
+    // Booking id awaiting a second click before it is actually cancelled
+    pending_cancel: Option<String>,
+    // Most recent booking mutations, newest last, capped at UNDO_LIMIT
+    history: Vec<UndoAction>,
+    price_filter: (Option<f64>, Option<f64>),
+    price_min_input: String,
+    price_max_input: String,
+    email_preview: Option<String>,
+    show_form: ShowForm,
+    modify_id_input: String,
+    // Booking being re-seated and the seats chosen for it so far
+    modifying: Option<(String, Vec<(usize, usize)>)>,
+    customer_history_input: String,
+    // Name last searched for; results follow the live bookings rather than a snapshot
+    customer_history_query: Option<String>,
+    seat_popularity: bool,
+    // Where the user asked to go while a booking was half-entered; set until they confirm or stay
+    pending_view_change: Option<View>,
+}
+
+// Raw inputs of the AddShow view, validated on CreateShow
+#[derive(Debug, Clone, Default)]
+struct ShowForm {
+    name: String,
+    date: String,
+    time: String,
+    hall: String,
+    price: String,
+    rows: String,
+    cols: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ShowField {
+    Name,
+    Date,
+    Time,
+    Hall,
+    Price,
+    Rows,
+    Cols,
+}
+
+// A seat's (row, col) position with its place in the sale order, if it had one
+type FreedSeat = ((usize, usize), Option<u64>);
+
+// A reversible booking mutation kept on the undo stack
+#[derive(Debug, Clone)]
+enum UndoAction {
+    Booked(Vec<String>), // ids of the bookings created
+    Cancelled(Vec<(Booking, Vec<FreedSeat>)>), // removed bookings with the seats they held
+}
+
+// One line of audit.log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuditEntry {
+    action: String,
+    booking_id: String,
+    seats: Vec<String>,
+    timestamp: String, // RFC 3339
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refund: Option<f64>, // amount owed back, on cancellations
+}
+
+// Aggregates behind the statistics view, refreshed on booking mutations instead of on every render
+#[derive(Debug, Clone, Default)]
+struct StatsCache {
+    total_bookings: usize,
+    total_revenue: f64,
+    available_seats: usize,
+    occupancy: Vec<(usize, usize)>, // (booked, capacity) per show id
+    revenue_by_group: Vec<(String, f64)>,
+    recent_activity: Vec<AuditEntry>, // newest first
+    revenue_by_show: Vec<f64>, // indexed by show id
+}
+
+// Look-and-feel settings, kept in preferences.json so resetting booking data leaves them alone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Preferences {
+    seat_mode: SeatRenderMode,
+    keep_booking_for_show: bool,
+    price_format: PriceFormat,
+    kiosk_mode: bool,
+    light_theme: bool,
+    date_format: DateFormat,
+    // Unattended bookings free their seats this long after showtime
+    no_show_release_minutes: i64,
+    // e.g. 0.05 for 5%, added on top of the discounted price
+    service_charge_rate: f64,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self { seat_mode: SeatRenderMode::Emoji, keep_booking_for_show: true, price_format: PriceFormat::default(), kiosk_mode: false, light_theme: false, date_format: DateFormat::Numeric, no_show_release_minutes: 30, service_charge_rate: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct PriceFormat {
+    currency: String, // ISO code such as "LKR"/"USD", or a symbol such as "€"
+    thousands_separator: char,
+    decimal_separator: char,
+}
+
+impl Default for PriceFormat {
+    fn default() -> Self {
+        Self { currency: "LKR".to_string(), thousands_separator: ',', decimal_separator: '.' }
+    }
+}
+
+impl PriceFormat {
+    // e.g. "LKR 1,500,000.00" or "€1,500,000.00"; refunds keep their sign as "LKR -250.00"
+    fn format(&self, amount: f64) -> String {
+        let fixed = format!("{:.2}", amount.abs());
+        let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, "00"));
+        let mut grouped = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push(self.thousands_separator);
+            }
+            grouped.push(digit);
+        }
+        let sign = if amount < 0.0 && fixed != "0.00" { "-" } else { "" };
+        // Letter codes are spaced from the amount, symbols sit right against it
+        let gap = if self.currency.chars().all(|c| c.is_ascii_alphabetic()) { " " } else { "" };
+        format!("{}{}{}{}{}{}", self.currency, gap, sign, grouped, self.decimal_separator, fraction)
+    }
+}
+
+impl Preferences {
+    fn load() -> Self {
+        fs::read_to_string("preferences.json").ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        write_file_atomic("preferences.json", serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum DateFormat {
+    Numeric, // 15-03-2024, as stored
+    Long,    // Mar 15, 2024
+}
+
+impl DateFormat {
+    fn format(self, date: NaiveDate) -> String {
+        match self {
+            DateFormat::Numeric => date.format("%d-%m-%Y").to_string(),
+            DateFormat::Long => date.format("%b %-d, %Y").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum SeatRenderMode {
+    Emoji,
+    Numeric,
+}
+
+// Transient marking layered over a seat's booked/free state when drawing it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SeatHighlight {
+    None,
+    Selected,
+    JustBooked,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 enum View {
     Home,
     ShowSelection,
@@ -66,226 +515,1303 @@ enum View {
     ViewSeats,
     Records,
     Statistics,
+    SeasonPass,
+    AddShow,
+    ModifyBooking,
+    CustomerHistory,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     ChangeView(View),
+    ConfirmLeave,
+    CancelLeave,
     SelectShow(usize),
     SelectSeat(usize, usize),
+    SeatCodeChanged(String),
+    SelectSeatByCode,
+    ClearSelection,
     CustomerNameChanged(String),
+    GroupNameChanged(String),
+    PhoneChanged(String),
+    TenderedChanged(String),
+    FilterRecordsByGroup(Option<String>),
+    RecordsFilterChanged(String),
+    SeatLookupChanged(String),
     ConfirmBooking,
+    ConfirmOverbooking,
     BookingIdChanged(String),
+    RequestCancel,
     CancelBookingConfirm,
+    Undo,
     ExportRecords,
+    ExportCsv,
+    PrintAllTickets,
+    ExportStats,
+    ImportRecords,
+    ResetConfirmChanged(String),
+    ClearAllBookings,
+    AutoSave,
+    ToggleSeatMode,
+    PreviewShowSeats(usize),
+    ToggleSeatPopularity(bool),
+    ToggleGridOrientation,
+    MarkAttended(String),
+    PreviewEmail(String),
+    CloseEmailPreview,
+    ToggleKeepBooking(bool),
+    ToggleKioskMode(bool),
+    ToggleTheme,
+    ToggleDateFormat,
+    FinalizeBooking(String),
+    TogglePassShow(usize),
+    PassSeatChanged(String),
+    ConfirmSeasonPass,
+    FilterByTag(String),
+    ClearTagFilter,
+    ToggleUpcomingOnly(bool),
+    PriceMinChanged(String),
+    PriceMaxChanged(String),
+    SetPricePreset(Option<f64>, Option<f64>),
+    ShowFormChanged(ShowField, String),
+    ModifyIdChanged(String),
+    LoadBookingForModify,
+    ModifySelectSeat(usize, usize),
+    ConfirmModify,
+    CustomerHistoryChanged(String),
+    SearchCustomerHistory,
+    CreateShow,
 }
 
-impl Sandbox for TheatreApp {
+impl Application for TheatreApp {
+    type Executor = executor::Default;
     type Message = Message;
+    type Theme = Theme;
+    type Flags = Vec<Show>;
+
+    // Seat counts are rebuilt from the saved bookings
+    fn new(shows: Vec<Show>) -> (Self, Command<Message>) {
+        for show in shows.iter().filter(|show| show.starts_at().is_none()) {
+            eprintln!("warning: show \"{}\" has an unparseable date/time \"{} {}\"; it will always be listed as upcoming", show.name, show.date, show.time);
+        }
+        let mut app = Self::with_shows(shows, Preferences::load(), SeatPalette::load());
+        app.load_state();
+        app.refresh_stats();
+        app.last_saved = serde_json::to_string_pretty(&app.bookings).unwrap_or_default();
+        (app, Command::none())
+    }
+
+    fn title(&self) -> String { "Premium Theatre Reservation System".to_string() }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        self.handle_message(message);
+        Command::none()
+    }
+
+    // FIXED: Added '_ for lifetime elision
+    fn view(&self) -> Element<'_, Message> {
+        let content = match self.current_view {
+            View::Home => self.home_view(),
+            View::ShowSelection => self.show_selection_view(),
+            View::Booking => self.booking_view(),
+            View::CancelBooking => self.cancel_booking_view(),
+            View::ViewSeats => self.view_seats(),
+            View::Records => self.records_view(),
+            View::Statistics => self.statistics_view(),
+            View::SeasonPass => self.season_pass_view(),
+            View::AddShow => self.add_show_view(),
+            View::ModifyBooking => self.modify_booking_view(),
+            View::CustomerHistory => self.customer_history_view(),
+        };
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(20)
+            .center_x()
+            .center_y()
+            .style(container_dark_style)
+            .into()
+    }
+
+    fn theme(&self) -> Theme { self.theme.clone() }
+
+    fn subscription(&self) -> Subscription<Message> {
+        iced::time::every(AUTO_SAVE_INTERVAL).map(|_| Message::AutoSave)
+    }
+}
 
-    fn new() -> Self {
-        let shows = vec![
-            Show { id: 0, name: "Dune: Part Two".to_string(), date: "15-03-2024".to_string(), time: "18:00".to_string(), hall: "Hall 1".to_string(), price: 1500.0, available_seats: 20 },
-            Show { id: 1, name: "Oppenheimer".to_string(), date: "20-03-2024".to_string(), time: "20:30".to_string(), hall: "Hall 2".to_string(), price: 2250.0, available_seats: 20 },
-            Show { id: 2, name: "Barbie".to_string(), date: "22-03-2024".to_string(), time: "19:00".to_string(), hall: "Hall 3".to_string(), price: 2000.0, available_seats: 20 },
-            Show { id: 3, name: "Deadpool & Wolverine".to_string(), date: "25-03-2024".to_string(), time: "21:00".to_string(), hall: "Hall 4".to_string(), price: 1500.0, available_seats: 20 },
-            Show { id: 4, name: "Inside Out 2".to_string(), date: "28-03-2024".to_string(), time: "17:30".to_string(), hall: "Hall 5".to_string(), price: 1500.0, available_seats: 20 },
-        ];
-
-        let seats = (0..5).map(|_| {
-            (0..4).map(|row| {
-                (0..5).map(|col| Seat {
-                    row: char::from_u32('A' as u32 + row as u32).unwrap(),
-                    col: col + 1,
-                    is_booked: false,
-                    booking_id: None,
-                }).collect()
-            }).collect()
-        }).collect();
-
-        Self {
+impl TheatreApp {
+    // Fresh app over the given lineup with every seat free; new() then loads saved bookings into it
+    fn with_shows(mut shows: Vec<Show>, prefs: Preferences, palette: SeatPalette) -> Self {
+        let seats: Vec<Vec<Vec<Seat>>> = shows.iter().map(|show| new_seat_grid(show.rows, show.cols)).collect();
+        for (show, grid) in shows.iter_mut().zip(&seats) {
+            show.available_seats = sellable_seats(grid);
+        }
+
+        let mut app = Self {
             current_view: View::Home,
             shows,
             bookings: Vec::new(),
             seats,
             selected_show: None,
-            selected_seat: None,
+            selected_seats: HashMap::new(),
             customer_name: String::new(),
             booking_id_input: String::new(),
             error_message: None,
             success_message: None,
-        }
+            palette,
+            prefs,
+            max_seats_per_booking: 6,
+            last_booked: Vec::new(),
+            theme: Theme::Dark,
+            stats_cache: StatsCache::default(),
+            last_confirm_instant: None,
+            pass_shows: Vec::new(),
+            pass_seat_input: String::new(),
+            tag_filter: None,
+            show_upcoming_only: true,
+            seat_code_input: String::new(),
+            group_input: String::new(),
+            phone_input: String::new(),
+            tendered_input: String::new(),
+            records_group_filter: None,
+            records_filter: String::new(),
+            seat_lookup_input: String::new(),
+            reset_confirm_input: String::new(),
+            last_saved: String::new(),
+            pending_cancel: None,
+            history: Vec::new(),
+            price_filter: (None, None),
+            price_min_input: String::new(),
+            price_max_input: String::new(),
+            email_preview: None,
+            show_form: ShowForm::default(),
+            modify_id_input: String::new(),
+            modifying: None,
+            customer_history_input: String::new(),
+            customer_history_query: None,
+            seat_popularity: false,
+            pending_view_change: None,
+        };
+        if app.prefs.light_theme { app.theme = Theme::Light; }
+        app
     }
 
-    fn title(&self) -> String { "Premium Theatre Reservation System".to_string() }
-
-    fn update(&mut self, message: Message) {
-        self.error_message = None;
-        self.success_message = None;
+    fn handle_message(&mut self, message: Message) {
+        // Auto-save ticks must not wipe the messages the user is reading
+        if !matches!(message, Message::AutoSave) {
+            self.error_message = None;
+            self.success_message = None;
+        }
+        self.release_no_shows();
+        self.expire_kiosk_reservations();
 
         match message {
             Message::ChangeView(view) => {
-                self.current_view = view;
-                self.customer_name.clear();
-                self.booking_id_input.clear();
-                self.selected_seat = None;
+                if self.booking_in_progress() && view != View::Booking {
+                    self.pending_view_change = Some(view);
+                    return;
+                }
+                self.change_view(view);
             }
+            Message::ConfirmLeave => {
+                let Some(view) = self.pending_view_change.take() else { return };
+                self.change_view(view);
+            }
+            Message::CancelLeave => self.pending_view_change = None,
             Message::SelectShow(id) => {
+                if id >= self.shows.len() {
+                    return;
+                }
+                if self.is_sold_out(id) {
+                    self.error_message = Some(format!("{} is sold out", self.shows[id].name));
+                    return;
+                }
                 self.selected_show = Some(id);
                 self.current_view = View::Booking;
+                // The highlighted positions belong to the previously booked show
+                self.last_booked.clear();
             }
-            Message::SelectSeat(row, col) => {
+            Message::SelectSeat(row, col) => self.select_seat(row, col),
+            Message::SeatCodeChanged(code) => self.seat_code_input = code,
+            Message::SelectSeatByCode => match parse_seat_code(&self.seat_code_input) {
+                Some((row, col)) => {
+                    if self.current_view == View::ModifyBooking { self.modify_select_seat(row, col) } else { self.select_seat(row, col) }
+                    self.seat_code_input.clear();
+                }
+                None => self.error_message = Some("Enter the seat as Row+Col, e.g. A3".to_string()),
+            },
+            Message::ClearSelection => {
                 if let Some(show_id) = self.selected_show {
-                    if !self.seats[show_id][row][col].is_booked {
-                        self.selected_seat = Some((row, col));
-                    }
+                    self.selected_seats.remove(&show_id);
                 }
             }
             Message::CustomerNameChanged(name) => self.customer_name = name,
+            Message::GroupNameChanged(group) => self.group_input = group,
+            Message::PhoneChanged(phone) => self.phone_input = phone,
+            Message::TenderedChanged(amount) => self.tendered_input = amount,
+            Message::FilterRecordsByGroup(group) => self.records_group_filter = group,
+            Message::RecordsFilterChanged(filter) => self.records_filter = filter,
+            Message::SeatLookupChanged(query) => self.seat_lookup_input = query,
             Message::ConfirmBooking => {
-                if let (Some(show_id), Some((row, col))) = (self.selected_show, self.selected_seat) {
-                    if self.customer_name.trim().is_empty() {
-                        self.error_message = Some("Please enter customer name".to_string());
+                if self.confirm_debounced() { return; }
+                let Some(show_id) = self.selected_show else { return };
+                if !self.show_exists(show_id) {
+                    self.error_message = Some("Show unavailable".to_string());
+                    return;
+                }
+                let selection = self.selection(show_id).to_vec();
+                if selection.is_empty() {
+                    self.error_message = Some("Please select at least one seat".to_string());
+                    return;
+                }
+                if let Err(e) = validate_customer_name(&self.customer_name) {
+                    self.error_message = Some(e);
+                    return;
+                }
+                let phone = match validate_phone(&self.phone_input) {
+                    Ok(phone) => phone,
+                    Err(e) => {
+                        self.error_message = Some(e);
+                        return;
+                    }
+                };
+
+                // Re-check right before committing: a seat may have been taken since it was
+                // selected (e.g. by an import), and booking it again would overwrite that booking
+                let grid = &self.seats[show_id];
+                let taken: Vec<(usize, usize)> = selection.iter().copied()
+                    .filter(|&(r, c)| grid.get(r).and_then(|row| row.get(c)).is_none_or(|seat| !seat.is_bookable()))
+                    .collect();
+                if !taken.is_empty() {
+                    let codes: Vec<String> = taken.iter()
+                        .map(|&(r, c)| grid.get(r).and_then(|row| row.get(c)).map_or_else(|| "?".to_string(), |s| format!("{}{}", s.row, s.col)))
+                        .collect();
+                    self.error_message = Some(format!("Seat(s) {} are no longer available; please review your selection", codes.join(", ")));
+                    if let Some(selected) = self.selected_seats.get_mut(&show_id) {
+                        selected.retain(|pos| !taken.contains(pos));
+                    }
+                    return;
+                }
+
+                let (gross_price, price) = self.compute_total(show_id, &selection);
+                let service_charge = self.service_charge_for(price);
+                // Kiosk reservations are paid later at the counter, so cash only applies to direct sales
+                let payment = if self.prefs.kiosk_mode { Ok(None) } else { cash_change(price + service_charge, &self.tendered_input, &self.prefs.price_format) };
+                let payment = match payment {
+                    Ok(payment) => payment,
+                    Err(e) => {
+                        self.error_message = Some(e);
                         return;
                     }
+                };
 
-                    let booking_id = Uuid::new_v4().to_string();
+                let booking_id = self.new_booking_code();
+                let mut seat_codes = Vec::new();
+                let mut seat_tiers = Vec::new();
+                for (order, &(row, col)) in (self.next_booking_order()..).zip(&selection) {
                     let seat = &mut self.seats[show_id][row][col];
-                    
                     seat.is_booked = true;
                     seat.booking_id = Some(booking_id.clone());
+                    seat.booking_order = Some(order);
+                    seat_codes.push(format!("{}{}", seat.row, seat.col));
+                    seat_tiers.push(seat.tier);
+                }
 
-                    let booking = Booking {
-                        id: booking_id.clone(),
-                        show_id,
-                        customer_name: self.customer_name.clone(),
-                        seat: format!("{}{}", seat.row, seat.col),
-                        booking_time: Local::now().format("%d-%m-%Y %H:%M:%S").to_string(),
-                        price: self.shows[show_id].price,
-                    };
+                let booking = Booking {
+                    id: booking_id.clone(),
+                    show_id,
+                    customer_name: self.customer_name.clone(),
+                    seats: seat_codes,
+                    price,
+                    gross_price,
+                    seat_tiers,
+                    booking_time: Local::now().format("%d-%m-%Y %H:%M:%S").to_string(),
+                    attended: false,
+                    no_show: false,
+                    overbooked: false,
+                    pass_id: None,
+                    receipt_no: self.next_receipt_no(),
+                    group_name: self.group_name(),
+                    phone,
+                    expires_at: self.prefs.kiosk_mode.then(|| {
+                        (Local::now() + Duration::minutes(KIOSK_HOLD_MINUTES)).format("%d-%m-%Y %H:%M:%S").to_string()
+                    }),
+                    tendered: payment.map(|(tendered, _)| tendered),
+                    change: payment.map(|(_, change)| change),
+                    service_charge,
+                    grand_total: price + service_charge,
+                };
 
-                    self.bookings.push(booking.clone());
-                    self.shows[show_id].available_seats -= 1;
-                    self.save_ticket(&booking);
+                self.bookings.push(booking.clone());
+                self.push_history(UndoAction::Booked(vec![booking_id.clone()]));
+                append_audit("book", &booking, None);
+                let available_before = self.shows[show_id].available_seats;
+                self.shows[show_id].available_seats -= selection.len();
+                let saved = if booking.expires_at.is_some() { self.save_payment_slip(&booking) } else { self.save_ticket(&booking) };
+                if let Err(e) = saved {
+                    self.error_message = Some(format!("Ticket could not be saved: {}", e));
+                }
 
-                    self.success_message = Some(format!("Booking confirmed! ID: {}", booking_id));
-                    self.customer_name.clear();
-                    self.selected_seat = None;
+                self.refresh_stats();
+                self.last_confirm_instant = Some(Instant::now());
+                self.success_message = Some(if booking.expires_at.is_some() {
+                    format!("Seats reserved! Please pay at the counter within {} minutes. ID: {}", KIOSK_HOLD_MINUTES, booking_id)
+                } else {
+                    match booking.change {
+                        Some(change) => format!("Booking confirmed! Receipt {} | ID: {} | Change due: {}", booking.receipt_no, booking_id, self.format_price(change)),
+                        None => format!("Booking confirmed! Receipt {} | ID: {}", booking.receipt_no, booking_id),
+                    }
+                });
+                // Only the booking that crosses the threshold alerts, not every one after it
+                if self.crossed_capacity_alert(show_id, available_before) {
+                    append_audit("capacity_alert", &booking, None);
+                    if let Some(msg) = &mut self.success_message {
+                        msg.push_str(&format!("\n⚠️ {} is {:.0}% full", self.shows[show_id].hall, CAPACITY_ALERT_PCT));
+                    }
+                }
+                self.customer_name.clear();
+                self.phone_input.clear();
+                self.tendered_input.clear();
+                // Nothing is left to discard, so an open leave prompt no longer applies
+                self.pending_view_change = None;
+                self.selected_seats.remove(&show_id);
+                self.last_booked = selection;
+                if !self.prefs.keep_booking_for_show {
+                    self.selected_show = None;
+                    self.current_view = View::ShowSelection;
+                    self.last_booked.clear();
                 }
             }
-            Message::BookingIdChanged(id) => self.booking_id_input = id,
-            Message::CancelBookingConfirm => {
-                let booking_id = self.booking_id_input.trim();
-                if let Some(idx) = self.bookings.iter().position(|b| b.id == booking_id) {
-                    let show_id = self.bookings[idx].show_id;
-                    for row in &mut self.seats[show_id] {
-                        for seat in row {
-                            if seat.booking_id.as_deref() == Some(booking_id) {
-                                seat.is_booked = false;
-                                seat.booking_id = None;
-                            }
-                        }
+            Message::ConfirmOverbooking => {
+                if self.confirm_debounced() { return; }
+                let Some(show_id) = self.selected_show else { return };
+                if !self.show_exists(show_id) {
+                    self.error_message = Some("Show unavailable".to_string());
+                    return;
+                }
+                if let Err(e) = validate_customer_name(&self.customer_name) {
+                    self.error_message = Some(e);
+                    return;
+                }
+                let phone = match validate_phone(&self.phone_input) {
+                    Ok(phone) => phone,
+                    Err(e) => {
+                        self.error_message = Some(e);
+                        return;
                     }
-                    self.bookings.remove(idx);
-                    self.shows[show_id].available_seats += 1;
-                    self.success_message = Some("Booking cancelled successfully".to_string());
-                    self.booking_id_input.clear();
+                };
+                if self.shows[show_id].available_seats > 0 || self.overbook_remaining(show_id) == 0 {
+                    self.error_message = Some("Overbooking is not available for this show".to_string());
+                    return;
+                }
+
+                let price = self.shows[show_id].effective_price();
+                let service_charge = self.service_charge_for(price);
+                let booking = Booking {
+                    id: self.new_booking_code(),
+                    show_id,
+                    customer_name: self.customer_name.clone(),
+                    seats: Vec::new(),
+                    seat_tiers: Vec::new(),
+                    booking_time: Local::now().format("%d-%m-%Y %H:%M:%S").to_string(),
+                    price,
+                    gross_price: price,
+                    service_charge,
+                    grand_total: price + service_charge,
+                    attended: false,
+                    no_show: false,
+                    overbooked: true,
+                    pass_id: None,
+                    receipt_no: self.next_receipt_no(),
+                    group_name: self.group_name(),
+                    phone,
+                    expires_at: None,
+                    tendered: None,
+                    change: None,
+                };
+
+                self.bookings.push(booking.clone());
+                self.push_history(UndoAction::Booked(vec![booking.id.clone()]));
+                append_audit("overbook", &booking, None);
+                if let Err(e) = self.save_ticket(&booking) {
+                    self.error_message = Some(format!("Ticket could not be saved: {}", e));
+                }
+                self.refresh_stats();
+                self.last_confirm_instant = Some(Instant::now());
+                self.success_message = Some(format!("⚠️ Overbooked booking confirmed without a physical seat! Receipt {} | ID: {}", booking.receipt_no, booking.id));
+                self.customer_name.clear();
+                self.phone_input.clear();
+                self.tendered_input.clear();
+                self.pending_view_change = None;
+            }
+            Message::ToggleKioskMode(kiosk) => {
+                self.prefs.kiosk_mode = kiosk;
+                self.save_preferences();
+            }
+            Message::ToggleTheme => {
+                self.theme = if self.theme == Theme::Light { Theme::Dark } else { Theme::Light };
+                self.prefs.light_theme = self.theme == Theme::Light;
+                self.save_preferences();
+            }
+            Message::ToggleDateFormat => {
+                self.prefs.date_format = match self.prefs.date_format {
+                    DateFormat::Numeric => DateFormat::Long,
+                    DateFormat::Long => DateFormat::Numeric,
+                };
+                self.save_preferences();
+            }
+            Message::FinalizeBooking(id) => {
+                let Some(booking) = self.bookings.iter_mut().find(|b| b.id == id && b.expires_at.is_some()) else {
+                    self.error_message = Some("Reservation not found or already paid".to_string());
+                    return;
+                };
+                booking.expires_at = None;
+                let booking = booking.clone();
+                if let Err(e) = self.save_ticket(&booking) {
+                    self.error_message = Some(format!("Ticket could not be saved: {}", e));
+                }
+                self.success_message = Some(format!("Payment received for {}. Receipt {}", booking.customer_name, booking.receipt_no));
+            }
+            Message::ToggleKeepBooking(keep) => {
+                self.prefs.keep_booking_for_show = keep;
+                self.save_preferences();
+            }
+            Message::TogglePassShow(show_id) => {
+                if let Some(pos) = self.pass_shows.iter().position(|&id| id == show_id) {
+                    self.pass_shows.remove(pos);
                 } else {
-                    self.error_message = Some("Booking ID not found".to_string());
+                    self.pass_shows.push(show_id);
                 }
             }
-            Message::ExportRecords => {
-                self.export_records();
-                self.success_message = Some("Records exported to bookings_export.json".to_string());
+            Message::FilterByTag(tag) => {
+                self.tag_filter = if self.tag_filter.as_ref() == Some(&tag) { None } else { Some(tag) };
             }
-        }
-    }
+            Message::ClearTagFilter => self.tag_filter = None,
+            Message::ToggleUpcomingOnly(upcoming) => self.show_upcoming_only = upcoming,
+            Message::PriceMinChanged(value) => {
+                self.price_filter.0 = value.trim().parse().ok();
+                self.price_min_input = value;
+            }
+            Message::PriceMaxChanged(value) => {
+                self.price_filter.1 = value.trim().parse().ok();
+                self.price_max_input = value;
+            }
+            Message::SetPricePreset(min, max) => {
+                self.price_filter = (min, max);
+                self.price_min_input = min.map(|v| v.to_string()).unwrap_or_default();
+                self.price_max_input = max.map(|v| v.to_string()).unwrap_or_default();
+            }
+            Message::PassSeatChanged(seat) => self.pass_seat_input = seat,
+            Message::ModifyIdChanged(id) => {
+                self.modify_id_input = normalize_booking_id(&id);
+                self.modifying = None;
+            }
+            Message::LoadBookingForModify => {
+                match self.find_booking(&self.modify_id_input) {
+                    None => self.error_message = Some("Booking ID not found".to_string()),
+                    Some(b) if b.overbooked || b.no_show => self.error_message = Some("This booking holds no seat to change".to_string()),
+                    Some(b) => {
+                        let current = self.seats.get(b.show_id).map_or(Vec::new(), |grid| {
+                            grid.iter().enumerate().flat_map(|(r, row)| {
+                                row.iter().enumerate().filter(|(_, s)| s.booking_id.as_ref() == Some(&b.id)).map(move |(c, _)| (r, c))
+                            }).collect()
+                        });
+                        self.modifying = Some((b.id.clone(), current));
+                    }
+                }
+            }
+            Message::ModifySelectSeat(row, col) => self.modify_select_seat(row, col),
+            Message::CustomerHistoryChanged(name) => self.customer_history_input = name,
+            Message::SearchCustomerHistory => {
+                let name = self.customer_history_input.trim();
+                if name.is_empty() {
+                    self.error_message = Some("Please enter a customer name".to_string());
+                } else {
+                    self.customer_history_query = Some(name.to_string());
+                }
+            }
+            Message::ConfirmModify => {
+                let Some((id, selection)) = self.modifying.clone() else { return };
+                let Some(idx) = self.bookings.iter().position(|b| b.id == id) else {
+                    self.error_message = Some("Booking ID not found".to_string());
+                    return;
+                };
+                if selection.is_empty() {
+                    self.error_message = Some("Select at least one seat, or cancel the booking instead".to_string());
+                    return;
+                }
+                let show_id = self.bookings[idx].show_id;
+                if !self.show_exists(show_id) {
+                    self.error_message = Some("Show unavailable".to_string());
+                    return;
+                }
+                let grid = &self.seats[show_id];
+                // Re-check in case someone else booked one of the chosen seats meanwhile
+                if let Some(seat) = selection.iter().map(|&(r, c)| &grid[r][c]).find(|s| s.is_reserved || (s.is_booked && s.booking_id.as_ref() != Some(&id))) {
+                    self.error_message = Some(format!("Seat {}{} is already booked by someone else", seat.row, seat.col));
+                    return;
+                }
 
-    // FIXED: Added '_ for lifetime elision
-    fn view(&self) -> Element<'_, Message> {
-        let content = match self.current_view {
-            View::Home => self.home_view(),
-            View::ShowSelection => self.show_selection_view(),
-            View::Booking => self.booking_view(),
-            View::CancelBooking => self.cancel_booking_view(),
-            View::ViewSeats => self.view_seats(),
-            View::Records => self.records_view(),
-            View::Statistics => self.statistics_view(),
-        };
+                // Seats the booking keeps hold on to their place in the sales order
+                let kept_orders: Vec<((usize, usize), Option<u64>)> = selection.iter().map(|&(r, c)| ((r, c), self.seats[show_id][r][c].booking_order)).collect();
+                let released = free_booking_seats(&mut self.seats[show_id], &id).len();
+                let mut seat_codes = Vec::new();
+                let mut seat_tiers = Vec::new();
+                let mut new_orders = self.next_booking_order()..;
+                for ((row, col), kept) in kept_orders {
+                    let seat = &mut self.seats[show_id][row][col];
+                    seat.is_booked = true;
+                    seat.booking_id = Some(id.clone());
+                    seat.booking_order = kept.or_else(|| new_orders.next());
+                    seat_codes.push(format!("{}{}", seat.row, seat.col));
+                    seat_tiers.push(seat.tier);
+                }
+                let show = &mut self.shows[show_id];
+                show.available_seats = show.available_seats + released - selection.len();
 
-        container(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .padding(20)
-            .center_x()
-            .center_y()
-            .style(container_dark_style)
-            .into()
-    }
+                let (gross_price, mut price) = self.compute_total(show_id, &selection);
+                if self.bookings[idx].pass_id.is_some() { price = gross_price * (1.0 - SEASON_PASS_DISCOUNT); }
+                let service_charge = self.service_charge_for(price);
+                let booking = &mut self.bookings[idx];
+                booking.price = price;
+                booking.gross_price = gross_price;
+                booking.service_charge = service_charge;
+                booking.grand_total = price + service_charge;
+                booking.seats = seat_codes;
+                booking.seat_tiers = seat_tiers;
+                let booking = booking.clone();
+                append_audit("modify", &booking, None);
 
-    fn theme(&self) -> Theme { Theme::Dark }
-}
+                let saved = if booking.expires_at.is_some() { self.save_payment_slip(&booking) } else { self.save_ticket(&booking) };
+                if let Err(e) = saved {
+                    self.error_message = Some(format!("Ticket could not be saved: {}", e));
+                }
+                self.refresh_stats();
+                self.success_message = Some(format!("Seats changed to {} | New price {}", booking.seat_list(), self.format_price(booking.price)));
+                self.modifying = None;
+            }
+            Message::ShowFormChanged(field, value) => {
+                let form = &mut self.show_form;
+                match field {
+                    ShowField::Name => form.name = value,
+                    ShowField::Date => form.date = value,
+                    ShowField::Time => form.time = value,
+                    ShowField::Hall => form.hall = value,
+                    ShowField::Price => form.price = value,
+                    ShowField::Rows => form.rows = value,
+                    ShowField::Cols => form.cols = value,
+                }
+            }
+            Message::CreateShow => {
+                let form = &self.show_form;
+                let (name, date, time, hall) = (form.name.trim(), form.date.trim(), form.time.trim(), form.hall.trim());
+                if name.is_empty() || date.is_empty() || time.is_empty() || hall.is_empty() {
+                    self.error_message = Some("Name, date, time and hall are required".to_string());
+                    return;
+                }
+                let Some(price) = form.price.trim().parse::<f64>().ok().filter(|p| p.is_finite() && *p >= 0.0) else {
+                    self.error_message = Some("Price must be a non-negative number".to_string());
+                    return;
+                };
+                // Blank dimensions fall back to the standard 4x5 hall
+                let dimension = |value: &str, default: usize, max: usize| {
+                    if value.trim().is_empty() { Some(default) } else { value.trim().parse::<usize>().ok().filter(|n| (1..=max).contains(n)) }
+                };
+                let (Some(rows), Some(cols)) = (dimension(&form.rows, default_rows(), MAX_HALL_ROWS), dimension(&form.cols, default_cols(), MAX_HALL_COLS)) else {
+                    self.error_message = Some(format!("Rows must be 1-{} and seats per row 1-{}", MAX_HALL_ROWS, MAX_HALL_COLS));
+                    return;
+                };
 
-impl TheatreApp {
-    // FIXED: Added '_ to all return types
+                let show = Show {
+                    id: self.shows.len(),
+                    name: name.to_string(),
+                    date: date.to_string(),
+                    time: time.to_string(),
+                    hall: hall.to_string(),
+                    price,
+                    available_seats: 0,
+                    overbook_pct: 0.0,
+                    transpose_grid: false,
+                    tags: Vec::new(),
+                    aisle_after: default_aisles(),
+                    rows,
+                    cols,
+                    emoji: String::new(),
+                    price_multiplier: 1.0,
+                };
+                if show.starts_at().is_none() {
+                    self.error_message = Some("Use DD-MM-YYYY for the date and HH:MM for the time".to_string());
+                    return;
+                }
+
+                let grid = new_seat_grid(show.rows, show.cols);
+                let show = Show { available_seats: sellable_seats(&grid), ..show };
+                self.success_message = Some(format!("{} added on {} at {}", show.name, show.date, show.time));
+                self.shows.push(show);
+                self.seats.push(grid);
+                self.show_form = ShowForm::default();
+                self.refresh_stats();
+                if let Err(e) = self.save_shows() {
+                    self.error_message = Some(format!("Show list could not be saved: {}", e));
+                }
+            }
+            Message::ConfirmSeasonPass => {
+                if self.pass_shows.len() < 2 {
+                    self.error_message = Some("Select at least two shows for a season pass".to_string());
+                    return;
+                }
+                if let Err(e) = validate_customer_name(&self.customer_name) {
+                    self.error_message = Some(e);
+                    return;
+                }
+                let Some((row, col)) = parse_seat_code(&self.pass_seat_input) else {
+                    self.error_message = Some("Enter the seat as Row+Col, e.g. A3".to_string());
+                    return;
+                };
+                if let Some(&missing) = self.pass_shows.iter().find(|&&id| !self.show_exists(id)) {
+                    self.error_message = Some(format!("Show {} is no longer available", missing));
+                    return;
+                }
+                for &show_id in &self.pass_shows {
+                    match self.seats[show_id].get(row).and_then(|r| r.get(col)) {
+                        None => {
+                            self.error_message = Some(format!("Seat {} does not exist in {}", self.pass_seat_input.trim().to_uppercase(), self.shows[show_id].hall));
+                            return;
+                        }
+                        Some(seat) if seat.is_reserved => {
+                            self.error_message = Some(format!("Seat {}{} is reserved", seat.row, seat.col));
+                            return;
+                        }
+                        Some(seat) if seat.is_booked => {
+                            self.error_message = Some(format!("Seat {}{} is already booked for {}", seat.row, seat.col, self.shows[show_id].name));
+                            return;
+                        }
+                        Some(_) => {}
+                    }
+                }
+
+                let pass_id = Uuid::new_v4().to_string();
+                let booking_time = Local::now().format("%d-%m-%Y %H:%M:%S").to_string();
+                let mut pass_bookings = Vec::new();
+                for show_id in self.pass_shows.clone() {
+                    let booking_id = self.new_booking_code();
+                    let order = self.next_booking_order();
+                    let gross_price = self.shows[show_id].effective_price() * self.seats[show_id][row][col].tier.factor();
+                    let price = gross_price * (1.0 - SEASON_PASS_DISCOUNT);
+                    let service_charge = self.service_charge_for(price);
+                    let seat = &mut self.seats[show_id][row][col];
+                    seat.is_booked = true;
+                    seat.booking_id = Some(booking_id.clone());
+                    seat.booking_order = Some(order);
+
+                    let booking = Booking {
+                        id: booking_id,
+                        show_id,
+                        customer_name: self.customer_name.clone(),
+                        seats: vec![format!("{}{}", seat.row, seat.col)],
+                        seat_tiers: vec![seat.tier],
+                        booking_time: booking_time.clone(),
+                        price,
+                        gross_price,
+                        service_charge,
+                        grand_total: price + service_charge,
+                        attended: false,
+                        no_show: false,
+                        overbooked: false,
+                        pass_id: Some(pass_id.clone()),
+                        receipt_no: self.next_receipt_no(),
+                        group_name: None,
+                        phone: None,
+                        expires_at: None,
+                        tendered: None,
+                        change: None,
+                    };
+                    self.shows[show_id].available_seats -= 1;
+                    if let Err(e) = self.save_ticket(&booking) {
+                        self.error_message = Some(format!("Ticket could not be saved: {}", e));
+                    }
+                    pass_bookings.push(booking.id.clone());
+                    append_audit("book", &booking, None);
+                    self.bookings.push(booking);
+                }
+                self.push_history(UndoAction::Booked(pass_bookings));
+                self.refresh_stats();
+                self.success_message = Some(format!("Season pass confirmed for {} shows! Pass ID: {}", self.pass_shows.len(), pass_id));
+                self.pass_shows.clear();
+                self.pass_seat_input.clear();
+                self.customer_name.clear();
+            }
+            Message::BookingIdChanged(id) => {
+                self.booking_id_input = normalize_booking_id(&id);
+                self.pending_cancel = None;
+            }
+            Message::RequestCancel => {
+                if let Some(booking) = self.find_booking(&self.booking_id_input) {
+                    self.pending_cancel = Some(booking.id.clone());
+                } else {
+                    self.error_message = Some("Booking ID not found".to_string());
+                }
+            }
+            Message::CancelBookingConfirm => {
+                let Some(booking_id) = self.pending_cancel.take() else { return; };
+                if let Some(idx) = self.bookings.iter().position(|b| b.id == booking_id) {
+                    // Season pass bookings are linked, so cancelling one cancels the whole pass
+                    let pass_id = self.bookings[idx].pass_id.clone();
+                    let linked: Vec<Booking> = match &pass_id {
+                        Some(pass) => self.bookings.iter().filter(|b| b.pass_id.as_ref() == Some(pass)).cloned().collect(),
+                        None => vec![self.bookings[idx].clone()],
+                    };
+                    let mut cancelled = Vec::new();
+                    let mut refund_total = 0.0;
+                    for booking in &linked {
+                        let refund = self.refund_for(booking);
+                        refund_total += refund;
+                        let mut freed = Vec::new();
+                        if let (Some(show), Some(seats)) = (self.shows.get_mut(booking.show_id), self.seats.get_mut(booking.show_id)) {
+                            freed = free_booking_seats(seats, &booking.id);
+                            show.available_seats += freed.len();
+                        }
+                        append_audit("cancel", booking, Some(refund));
+                        cancelled.push((booking.clone(), freed));
+                    }
+                    self.push_history(UndoAction::Cancelled(cancelled));
+                    self.bookings.retain(|b| !linked.iter().any(|l| l.id == b.id));
+                    self.refresh_stats();
+                    let refund = self.format_price(refund_total);
+                    self.success_message = Some(if pass_id.is_some() {
+                        format!("Season pass cancelled ({} bookings). Refund due: {}", linked.len(), refund)
+                    } else {
+                        format!("Booking cancelled successfully. Refund due: {}", refund)
+                    });
+                    self.booking_id_input.clear();
+                } else {
+                    self.error_message = Some("Booking ID not found".to_string());
+                }
+            }
+            Message::Undo => match self.history.pop() {
+                None => self.error_message = Some("Nothing to undo".to_string()),
+                Some(UndoAction::Booked(ids)) => {
+                    let mut undone = 0;
+                    for id in &ids {
+                        let Some(idx) = self.bookings.iter().position(|b| &b.id == id) else { continue };
+                        let booking = self.bookings.remove(idx);
+                        append_audit("undo_booking", &booking, None);
+                        if let (Some(show), Some(seats)) = (self.shows.get_mut(booking.show_id), self.seats.get_mut(booking.show_id)) {
+                            show.available_seats += free_booking_seats(seats, &booking.id).len();
+                        }
+                        undone += 1;
+                    }
+                    self.refresh_stats();
+                    self.success_message = Some(match undone {
+                        0 => "Nothing to undo: the booking no longer exists".to_string(),
+                        1 => "Booking undone".to_string(),
+                        n => format!("Booking undone ({} bookings removed)", n),
+                    });
+                }
+                Some(UndoAction::Cancelled(cancelled)) => self.undo_cancellation(cancelled),
+            },
+            Message::AutoSave => self.auto_save(),
+            Message::ResetConfirmChanged(value) => self.reset_confirm_input = value,
+            Message::ClearAllBookings => {
+                if self.reset_confirm_input.trim() != "RESET" {
+                    self.error_message = Some("Type RESET to confirm clearing all bookings".to_string());
+                    return;
+                }
+                match self.clear_all_bookings() {
+                    Ok(backup) => self.success_message = Some(format!("All bookings cleared. Backup saved to {}", backup)),
+                    Err(e) => self.error_message = Some(format!("Bookings were not cleared: {}", e)),
+                }
+            }
+            Message::ImportRecords => {
+                match self.import_records("bookings_export.json") {
+                    Ok((imported, skipped)) => {
+                        self.refresh_stats();
+                        self.success_message = Some(format!("Imported {}, skipped {}", imported, skipped));
+                    }
+                    Err(e) => self.error_message = Some(format!("Import failed: {}", e)),
+                }
+            }
+            Message::ExportCsv => {
+                match self.export_records_csv() {
+                    Ok(()) => self.success_message = Some("Records exported to bookings_export.csv".to_string()),
+                    Err(e) => self.error_message = Some(format!("Export failed: {}", e)),
+                }
+            }
+            Message::PrintAllTickets => {
+                match self.export_all_tickets() {
+                    Ok((written, failed)) if failed > 0 => self.error_message = Some(format!("{} tickets written to all_tickets.txt, {} individual files failed", written, failed)),
+                    Ok((written, _)) => self.success_message = Some(format!("{} tickets written to all_tickets.txt", written)),
+                    Err(e) => self.error_message = Some(format!("Printing tickets failed: {}", e)),
+                }
+            }
+            Message::ExportStats => {
+                match self.export_stats() {
+                    Ok(()) => self.success_message = Some("Statistics exported to statistics_report.txt".to_string()),
+                    Err(e) => self.error_message = Some(format!("Export failed: {}", e)),
+                }
+            }
+            Message::ExportRecords => {
+                match self.export_records() {
+                    Ok(()) => self.success_message = Some("Records exported to bookings_export.json".to_string()),
+                    Err(e) => self.error_message = Some(format!("Export failed: {}", e)),
+                }
+            }
+            Message::PreviewEmail(id) => {
+                self.email_preview = self.bookings.iter().find(|b| b.id == id)
+                    .and_then(|b| Some(build_email_body(b, self.shows.get(b.show_id)?, &self.prefs.price_format)));
+            }
+            Message::CloseEmailPreview => self.email_preview = None,
+            Message::PreviewShowSeats(id) => {
+                if id < self.shows.len() {
+                    self.selected_show = Some(id);
+                }
+            }
+            Message::ToggleSeatPopularity(on) => self.seat_popularity = on,
+            Message::ToggleGridOrientation => {
+                if let Some(show) = self.selected_show.and_then(|id| self.shows.get_mut(id)) {
+                    show.transpose_grid = !show.transpose_grid;
+                    if let Err(e) = self.save_shows() {
+                        self.error_message = Some(format!("Grid orientation could not be saved: {}", e));
+                    }
+                }
+            }
+            Message::MarkAttended(id) => {
+                if !self.verify_ticket(&id) {
+                    self.error_message = Some(format!("No booking found for ticket {}", id));
+                } else if let Some(booking) = self.bookings.iter_mut().find(|b| b.id == id && !b.no_show) {
+                    booking.attended = true;
+                    self.success_message = Some(format!("{} marked as attended", booking.customer_name));
+                }
+            }
+            Message::ToggleSeatMode => {
+                self.prefs.seat_mode = match self.prefs.seat_mode {
+                    SeatRenderMode::Emoji => SeatRenderMode::Numeric,
+                    SeatRenderMode::Numeric => SeatRenderMode::Emoji,
+                };
+                self.save_preferences();
+            }
+        }
+    }
+
+    // Leaves the current screen, dropping anything half-typed on it
+    fn change_view(&mut self, view: View) {
+        self.current_view = view;
+        self.pending_view_change = None;
+        self.customer_name.clear();
+        self.booking_id_input.clear();
+        self.group_input.clear();
+        self.phone_input.clear();
+        self.tendered_input.clear();
+        self.email_preview = None;
+        self.pending_cancel = None;
+        self.modify_id_input.clear();
+        self.modifying = None;
+        self.last_booked.clear();
+        self.reset_confirm_input.clear();
+        self.customer_history_input.clear();
+        self.customer_history_query = None;
+    }
+
+    // Customer details typed on the booking screen that leaving would clear. Picked seats don't
+    // count: each show's selection is kept for when the user comes back to it.
+    fn booking_in_progress(&self) -> bool {
+        self.current_view == View::Booking
+            && [&self.customer_name, &self.phone_input, &self.tendered_input].iter().any(|input| !input.trim().is_empty())
+    }
+
+    // Guards handlers that index shows/seats by an id that may have gone stale
+    fn show_exists(&self, show_id: usize) -> bool {
+        show_id < self.shows.len() && show_id < self.seats.len()
+    }
+
+    fn select_seat(&mut self, row: usize, col: usize) {
+        let Some(show_id) = self.selected_show else { return };
+        self.last_booked.clear();
+        if !self.show_exists(show_id) {
+            self.error_message = Some("Show unavailable".to_string());
+            return;
+        }
+        match self.seats[show_id].get(row).and_then(|r| r.get(col)) {
+            Some(seat) if seat.is_reserved => self.error_message = Some(format!("Seat {}{} is reserved and not available for public booking", seat.row, seat.col)),
+            Some(seat) if !seat.is_booked => {
+                let selection = self.selected_seats.entry(show_id).or_default();
+                match selection.iter().position(|&s| s == (row, col)) {
+                    Some(pos) => { selection.remove(pos); }
+                    None if selection.len() >= self.max_seats_per_booking => {
+                        self.error_message = Some(format!("You can select at most {} seats", self.max_seats_per_booking));
+                    }
+                    None => selection.push((row, col)),
+                }
+            }
+            Some(seat) => self.error_message = Some(format!("Seat {}{} is already taken", seat.row, seat.col)),
+            None => self.error_message = Some("That seat does not exist in this hall".to_string()),
+        }
+    }
+
+    fn modify_select_seat(&mut self, row: usize, col: usize) {
+        let Some((id, selection)) = &mut self.modifying else { return };
+        let Some(show_id) = self.bookings.iter().find(|b| &b.id == id).map(|b| b.show_id) else { return };
+        match self.seats.get(show_id).and_then(|grid| grid.get(row)?.get(col)) {
+            Some(seat) if seat.is_reserved => self.error_message = Some("That seat is reserved".to_string()),
+            Some(seat) if seat.is_booked && seat.booking_id.as_ref() != Some(id) => {
+                self.error_message = Some("That seat is already booked".to_string());
+            }
+            Some(_) => match selection.iter().position(|&s| s == (row, col)) {
+                Some(pos) => { selection.remove(pos); }
+                None if selection.len() >= self.max_seats_per_booking => {
+                    self.error_message = Some(format!("You can select at most {} seats", self.max_seats_per_booking));
+                }
+                None => selection.push((row, col)),
+            },
+            None => self.error_message = Some("That seat does not exist in this hall".to_string()),
+        }
+    }
+
+    // Stands in for the seat buttons when a hall is too large to draw (see MAX_GRID_RENDER_SEATS)
+    fn seat_code_entry(&self, grid: &[Vec<Seat>], selected: &[(usize, usize)]) -> Element<'_, Message> {
+        let seat_count = grid.len() * grid.first().map_or(0, Vec::len);
+        let selected = selected.iter()
+            .filter_map(|&(r, c)| grid.get(r)?.get(c))
+            .map(|seat| format!("{}{}", seat.row, seat.col))
+            .collect::<Vec<_>>().join(", ");
+        column![
+            text(format!("⚠️ This hall has {} seats, too many to draw. Enter a seat code instead.", seat_count)).style(Color::from_rgb(0.95, 0.7, 0.2)),
+            row![
+                text_input("Seat (e.g. B3)", &self.seat_code_input).on_input(Message::SeatCodeChanged).on_submit(Message::SelectSeatByCode).padding(10).width(200),
+                button("Select").on_press(Message::SelectSeatByCode).padding(10),
+            ].spacing(10),
+            text(format!("Selected: {}", if selected.is_empty() { "none" } else { &selected })).size(14),
+        ].spacing(10).align_items(Alignment::Center).into()
+    }
+
+    fn selection(&self, show_id: usize) -> &[(usize, usize)] {
+        self.selected_seats.get(&show_id).map_or(&[], Vec::as_slice)
+    }
+
+    // Resolves "Hall 1 / A3" to the owner of that seat in every show running in the hall
+    fn lookup_seat(&self, query: &str) -> Result<Vec<String>, String> {
+        let (hall, code) = query.split_once('/').ok_or("Enter as Hall / Row+Col, e.g. Hall 1 / A3")?;
+        let (row, col) = parse_seat_code(code).ok_or("Enter as Row+Col, e.g. A3")?;
+        let hall = hall.trim();
+        let shows: Vec<&Show> = self.shows.iter().filter(|s| s.hall.eq_ignore_ascii_case(hall)).collect();
+        if shows.is_empty() {
+            return Err(format!("No show is running in {}", hall));
+        }
+        shows.into_iter().map(|show| {
+            let seat = self.seats.get(show.id).and_then(|grid| grid.get(row)?.get(col))
+                .ok_or_else(|| format!("Seat {} does not exist in {}", code.trim().to_uppercase(), show.hall))?;
+            Ok(match seat.booking_id.as_ref().and_then(|id| self.bookings.iter().find(|b| &b.id == id)) {
+                Some(b) => format!("🎬 {} | 💺 {}{} | 👤 {} | 🎫 {} | 🕒 {}", show.name, seat.row, seat.col, b.customer_name, b.id, b.booking_time),
+                None => format!("🎬 {} | 💺 {}{} | Seat is free", show.name, seat.row, seat.col),
+            })
+        }).collect()
+    }
+
+    fn next_booking_order(&self) -> u64 {
+        self.seats.iter().flatten().flatten().filter_map(|s| s.booking_order).max().unwrap_or(0) + 1
+    }
+
+    // Seat codes of the show's quickest-selling seats, earliest first
+    fn fastest_selling_seats(&self, show_id: usize, n: usize) -> Vec<String> {
+        let mut sold: Vec<&Seat> = self.seats.get(show_id).map_or(Vec::new(), |grid| grid.iter().flatten().filter(|s| s.is_booked && s.booking_order.is_some()).collect());
+        sold.sort_by_key(|s| s.booking_order);
+        sold.into_iter().take(n).map(|s| format!("{}{}", s.row, s.col)).collect()
+    }
+
+    fn customer_bookings(&self, name: &str) -> Vec<&Booking> {
+        let name = name.trim().to_lowercase();
+        self.bookings.iter().filter(|b| b.customer_name.trim().to_lowercase() == name).collect()
+    }
+
+    // "Total spent" in the customer history: what was actually paid, service charge included
+    fn customer_total(&self, name: &str) -> f64 {
+        self.customer_bookings(name).iter().map(|b| b.grand_total).sum()
+    }
+
+    fn group_name(&self) -> Option<String> {
+        Some(self.group_input.trim().to_string()).filter(|g| !g.is_empty())
+    }
+
+    // Box-office style receipt number, e.g. 2024-03-15-0042, restarting at 0001 each day
+    fn next_receipt_no(&self) -> String {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let last = self.bookings.iter()
+            .filter_map(|b| b.receipt_no.strip_prefix(&today)?.strip_prefix('-')?.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0);
+        format!("{}-{:04}", today, last + 1)
+    }
+
+    fn push_history(&mut self, action: UndoAction) {
+        self.history.push(action);
+        if self.history.len() > UNDO_LIMIT {
+            self.history.remove(0);
+        }
+    }
+
+    fn undo_cancellation(&mut self, cancelled: Vec<(Booking, Vec<FreedSeat>)>) {
+        let taken = cancelled.iter().find_map(|(booking, positions)| {
+            let grid = self.seats.get(booking.show_id)?;
+            positions.iter().map(|&((r, c), _)| &grid[r][c]).find(|seat| seat.is_booked)
+        });
+        if let Some(seat) = taken {
+            self.error_message = Some(format!("Cannot undo: seat {}{} has been booked again", seat.row, seat.col));
+            return;
+        }
+        if cancelled.iter().any(|(booking, _)| booking.show_id >= self.shows.len()) {
+            self.error_message = Some("Cannot undo: the show is no longer available".to_string());
+            return;
+        }
+
+        let restored = cancelled.len();
+        for (booking, positions) in cancelled {
+            for &((r, c), order) in &positions {
+                let seat = &mut self.seats[booking.show_id][r][c];
+                seat.is_booked = true;
+                seat.booking_id = Some(booking.id.clone());
+                seat.booking_order = order;
+            }
+            self.shows[booking.show_id].available_seats -= positions.len();
+            append_audit("undo_cancel", &booking, None);
+            self.bookings.push(booking);
+        }
+        self.refresh_stats();
+        self.success_message = Some(if restored == 1 { "Cancellation undone".to_string() } else { format!("Cancellation undone ({} bookings restored)", restored) });
+    }
+
+    fn confirm_debounced(&self) -> bool {
+        self.last_confirm_instant.is_some_and(|t| t.elapsed() < CONFIRM_DEBOUNCE)
+    }
+
+    // (gross, net) for the given seats: tier-adjusted seat prices, less any group discount
+    fn compute_total(&self, show_id: usize, seats: &[(usize, usize)]) -> (f64, f64) {
+        let (Some(show), Some(grid)) = (self.shows.get(show_id), self.seats.get(show_id)) else { return (0.0, 0.0) };
+        let gross: f64 = seats.iter()
+            .filter_map(|&(r, c)| grid.get(r)?.get(c))
+            .map(|seat| show.effective_price() * seat.tier.factor())
+            .sum();
+        (gross, gross * (1.0 - group_discount_rate(seats.len())))
+    }
+
+    fn seat_selection_cap(&self, show_id: usize) -> usize {
+        self.shows.get(show_id).map_or(0, |show| show.available_seats.min(self.max_seats_per_booking))
+    }
+
+    // e.g. "3 of 20 free (2 selected)", counting the current selection as already taken
+    fn seats_remaining_text(&self, show_id: usize) -> String {
+        let grid = self.seats.get(show_id).map_or(&[][..], Vec::as_slice);
+        let total = grid.iter().flatten().filter(|s| !s.is_reserved).count();
+        let free = grid.iter().flatten().filter(|s| s.is_bookable()).count();
+        let selected = self.selection(show_id).len();
+        format!("💺 {} of {} free ({} selected)", free.saturating_sub(selected), total, selected)
+    }
+
+    // Live total for the current selection; the same figures ConfirmBooking stores on the booking
+    fn total_preview_text(&self, show_id: usize) -> String {
+        let (gross, net) = self.compute_total(show_id, self.selection(show_id));
+        let service_charge = self.service_charge_for(net);
+        let mut parts = Vec::new();
+        if gross > net {
+            parts.push(format!("Subtotal: {}", self.format_price(gross)));
+            parts.push(format!("Group discount: -{}", self.format_price(gross - net)));
+        }
+        if service_charge > 0.0 {
+            parts.push(format!("Service charge ({:.0}%): {}", self.prefs.service_charge_rate * 100.0, self.format_price(service_charge)));
+        }
+        parts.push(format!("Total: {}", self.format_price(net + service_charge)));
+        parts.join(" | ")
+    }
+
+    // Charged on the price after group or season pass discounts; a negative or garbled rate in
+    // preferences.json charges nothing
+    fn service_charge_for(&self, net: f64) -> f64 {
+        let rate = self.prefs.service_charge_rate;
+        if rate.is_finite() && rate > 0.0 { net * rate } else { 0.0 }
+    }
+
+    fn selection_allowance_text(&self, show_id: usize) -> String {
+        match self.seat_selection_cap(show_id).saturating_sub(self.selection(show_id).len()) {
+            0 if self.selection(show_id).len() >= self.max_seats_per_booking => format!("Limit of {} seats per booking reached — confirm or clear your selection", self.max_seats_per_booking),
+            0 => "No more free seats — confirm or clear your selection".to_string(),
+            1 => "You can select 1 more seat".to_string(),
+            n => format!("You can select {} more seats", n),
+        }
+    }
+
+    // FIXED: Added '_ to all return types
     fn home_view(&self) -> Element<'_, Message> {
         column![
             text("🎬 Premium Theatre Reservation").size(48),
             text("Your ultimate movie booking experience").size(20),
             Space::with_height(40),
-            column![
+            scrollable(column![
                 menu_button("🎥 Browse Movies", Message::ChangeView(View::ShowSelection)),
                 menu_button("🎫 Book Seats", Message::ChangeView(View::ShowSelection)),
+                menu_button("🎟️ Season Pass", Message::ChangeView(View::SeasonPass)),
+                menu_button("➕ Add Show", Message::ChangeView(View::AddShow)),
                 menu_button("❌ Cancel Booking", Message::ChangeView(View::CancelBooking)),
+                menu_button("🔄 Change Seats", Message::ChangeView(View::ModifyBooking)),
+                menu_button("👤 Customer History", Message::ChangeView(View::CustomerHistory)),
                 menu_button("💺 View Seats", Message::ChangeView(View::ViewSeats)),
                 menu_button("📋 All Records", Message::ChangeView(View::Records)),
                 menu_button("📊 Statistics", Message::ChangeView(View::Statistics)),
-            ].spacing(15).align_items(Alignment::Center)
+            ].spacing(15).align_items(Alignment::Center).width(Length::Fill)),
+            checkbox("Kiosk mode (reserve now, pay at counter)", self.prefs.kiosk_mode).on_toggle(Message::ToggleKioskMode),
+            row![
+                button(if self.theme == Theme::Light { "🌙 Dark mode" } else { "☀️ Light mode" }).on_press(Message::ToggleTheme).padding(8),
+                button(if self.prefs.date_format == DateFormat::Numeric { "📅 Dates: Mar 15, 2024" } else { "📅 Dates: 15-03-2024" }).on_press(Message::ToggleDateFormat).padding(8),
+            ].spacing(10),
         ]
         .spacing(20).align_items(Alignment::Center).width(Length::Fill).into()
     }
 
     fn show_selection_view(&self) -> Element<'_, Message> {
-        let shows: Element<_> = self.shows.iter()
-            .fold(column![].spacing(15), |col, show| col.push(show_card(show)))
-            .into();
+        let (min_price, max_price) = self.price_filter;
+        let now = Local::now().naive_local();
+        let visible: Vec<&Show> = self.shows.iter()
+            // Shows whose date or time can't be parsed are always listed rather than silently hidden
+            .filter(|show| !self.show_upcoming_only || self.show_datetime(show.id).is_none_or(|starts| starts >= now))
+            .filter(|show| self.tag_filter.as_ref().is_none_or(|tag| show.tags.contains(tag)))
+            .filter(|show| min_price.is_none_or(|min| show.effective_price() >= min) && max_price.is_none_or(|max| show.effective_price() <= max))
+            .collect();
+        let shows: Element<_> = if self.shows.is_empty() {
+            text("No shows available").size(20).into()
+        } else if visible.is_empty() && self.show_upcoming_only {
+            text("No upcoming shows match the current filters — untick \"Show upcoming only\" to include past screenings").size(20).into()
+        } else if visible.is_empty() {
+            text("No shows match the current filters").size(20).into()
+        } else {
+            visible.into_iter()
+                .fold(column![].spacing(15), |col, show| col.push(show_card(show, &self.prefs.price_format, self.is_sold_out(show.id))))
+                .into()
+        };
 
-        column![
+        let mut all_tags: Vec<&String> = self.shows.iter().flat_map(|s| &s.tags).collect();
+        all_tags.sort();
+        all_tags.dedup();
+        let tag_bar = all_tags.into_iter().fold(
+            row![button("All").on_press(Message::ClearTagFilter).padding(6)].spacing(8),
+            |bar, tag| {
+                let label = if self.tag_filter.as_ref() == Some(tag) { format!("✔ {}", tag) } else { tag.clone() };
+                bar.push(button(text(label).size(14)).on_press(Message::FilterByTag(tag.clone())).padding(6))
+            },
+        );
+
+        let price_bar = row![
+            text(format!("💰 {}", self.prefs.price_format.currency)).size(14),
+            text_input("Min", &self.price_min_input).on_input(Message::PriceMinChanged).padding(6).width(90),
+            text_input("Max", &self.price_max_input).on_input(Message::PriceMaxChanged).padding(6).width(90),
+            button(text("Up to 1500").size(14)).on_press(Message::SetPricePreset(None, Some(1500.0))).padding(6),
+            button(text("1500–2000").size(14)).on_press(Message::SetPricePreset(Some(1500.0), Some(2000.0))).padding(6),
+            button(text("2000+").size(14)).on_press(Message::SetPricePreset(Some(2000.0), None)).padding(6),
+            button(text("Any price").size(14)).on_press(Message::SetPricePreset(None, None)).padding(6),
+        ].spacing(8).align_items(Alignment::Center);
+
+        let mut content = column![
             text("Now Showing").size(36),
+            tag_bar,
+            price_bar,
+            checkbox("Show upcoming only", self.show_upcoming_only).on_toggle(Message::ToggleUpcomingOnly),
             Space::with_height(20),
-            shows,
+            scrollable(shows).height(Length::Fill),
             Space::with_height(20),
             button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
-        ].width(Length::Fill).into()
+        ].width(Length::Fill);
+
+        if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
+        if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
+        content.into()
     }
 
     fn booking_view(&self) -> Element<'_, Message> {
-        if let Some(show_id) = self.selected_show {
-            let show = &self.shows[show_id];
-            let mut seat_grid = column![].spacing(10);
-            
-            for (r_idx, row) in self.seats[show_id].iter().enumerate() {
-                let mut seat_row = row![text(format!("{}", r_idx + 1)).size(16)].spacing(8);
-                for (c_idx, seat) in row.iter().enumerate() {
-                    let is_sel = self.selected_seat == Some((r_idx, c_idx));
-                    seat_row = seat_row.push(create_seat_button(seat, is_sel, r_idx, c_idx));
+        if let Some((show_id, show, grid)) = self.selected_show.and_then(|id| Some((id, self.shows.get(id)?, self.seats.get(id)?))) {
+            let (rows, cols) = (grid.len(), grid.first().map_or(0, Vec::len));
+
+            let seat_grid: Element<_> = if rows * cols > MAX_GRID_RENDER_SEATS {
+                self.seat_code_entry(grid, self.selection(show_id))
+            } else {
+                // A transposed grid draws each column as a line so wide halls fit the window; the seat matrix is unchanged
+                // Rows are labelled with their seat letter; when transposed each line is a seat column, so aisles become gaps between lines
+                let mut seat_grid = column![].spacing(10).align_items(Alignment::Center);
+                let (lines, per_line) = if show.transpose_grid { (cols, rows) } else { (rows, cols) };
+                for line in 0..lines {
+                    let label = if show.transpose_grid { format!("{}", line + 1) } else { grid[line][0].row.to_string() };
+                    let mut seat_row = row![text(label).size(16).width(20)].spacing(8).align_items(Alignment::Center);
+                    for i in 0..per_line {
+                        let (r_idx, c_idx) = if show.transpose_grid { (i, line) } else { (line, i) };
+                        let highlight = if self.selection(show_id).contains(&(r_idx, c_idx)) {
+                            SeatHighlight::Selected
+                        } else if self.last_booked.contains(&(r_idx, c_idx)) {
+                            SeatHighlight::JustBooked
+                        } else {
+                            SeatHighlight::None
+                        };
+                        seat_row = seat_row.push(create_seat_button(&grid[r_idx][c_idx], highlight, r_idx, c_idx, &self.palette, self.prefs.seat_mode, Message::SelectSeat));
+                        if !show.transpose_grid && show.aisle_after.contains(&(c_idx + 1)) && i + 1 < per_line {
+                            seat_row = seat_row.push(Space::with_width(24));
+                        }
+                    }
+                    seat_grid = seat_grid.push(seat_row);
+                    if show.transpose_grid && show.aisle_after.contains(&(line + 1)) && line + 1 < lines {
+                        seat_grid = seat_grid.push(Space::with_height(14));
+                    }
                 }
-                seat_grid = seat_grid.push(seat_row);
-            }
+                screen_and_seat_grid(seat_grid)
+            };
 
             let mut content = column![
-                text(format!("Booking: {}", show.name)).size(32),
-                text(format!("📅 {} | ⏰ {} | 🏛️ {} | 💰 LKR {:.2}", show.date, show.time, show.hall, show.price)).size(16),
+                text(format!("{} Booking: {}", show.emoji(), show.name)).size(32),
+                text(format!("📅 {} | ⏰ {} | 🏛️ {} | 💰 {}", show.display_date(self.prefs.date_format), show.time, show.hall, show.price_label(&self.prefs.price_format))).size(16),
+                text(self.seats_remaining_text(show_id)).size(16),
+                row![
+                    button(if self.prefs.seat_mode == SeatRenderMode::Emoji { "🔢 Show Seat Numbers" } else { "🟢 Show Seat Icons" })
+                        .on_press(Message::ToggleSeatMode).padding(8),
+                    button("🔄 Rotate Grid").on_press(Message::ToggleGridOrientation).padding(8),
+                ].spacing(10),
                 Space::with_height(20),
-                text("🎬 SCREEN").size(20),
-                Space::with_height(10),
                 seat_grid,
+                text(format!(
+                    "Gold border: VIP {} | Purple border: Premium {} | Standard {}",
+                    self.format_price(show.effective_price() * SeatTier::VIP.factor()),
+                    self.format_price(show.effective_price() * SeatTier::Premium.factor()),
+                    self.format_price(show.effective_price()),
+                )).size(14),
+                text(self.selection_allowance_text(show_id)).size(14),
+                text(self.total_preview_text(show_id)).size(16),
                 Space::with_height(20),
                 text_input("Enter your name", &self.customer_name).on_input(Message::CustomerNameChanged).padding(10),
+                text_input("Phone for SMS reminders (optional)", &self.phone_input).on_input(Message::PhoneChanged).padding(10),
+                text_input("Cash tendered (optional)", &self.tendered_input).on_input(Message::TenderedChanged).padding(10),
+                text_input("Corporate / group account (optional)", &self.group_input).on_input(Message::GroupNameChanged).padding(10),
+                checkbox("Keep booking for this show", self.prefs.keep_booking_for_show).on_toggle(Message::ToggleKeepBooking),
                 button("✅ Confirm Booking").on_press(Message::ConfirmBooking).padding(15),
+                button("Clear Selection").on_press(Message::ClearSelection).padding(10),
                 button("← Back").on_press(Message::ChangeView(View::ShowSelection)).padding(10)
             ].spacing(10).align_items(Alignment::Center);
 
+            if self.pending_view_change.is_some() {
+                content = content.push(container(column![
+                    text("Discard the customer details you entered?").size(18),
+                    row![
+                        button("🗑 Discard and leave").on_press(Message::ConfirmLeave).padding(10),
+                        button("Keep booking").on_press(Message::CancelLeave).padding(10),
+                    ].spacing(10),
+                ].spacing(8).padding(15).align_items(Alignment::Center)).style(container_card_style));
+            }
+
+            let overbook_left = self.overbook_remaining(show_id);
+            if self.is_sold_out(show_id) {
+                content = content.push(text("🚫 SOLD OUT — no seats left for this show").size(18).style(Color::from_rgb(0.9, 0.3, 0.3)));
+            } else if show.available_seats == 0 && overbook_left > 0 {
+                content = content
+                    .push(text(format!("⚠️ Hall is full — {} overbooked place(s) left without a physical seat", overbook_left)).style(Color::from_rgb(0.95, 0.7, 0.2)))
+                    .push(button("⚠️ Overbook").on_press(Message::ConfirmOverbooking).padding(10));
+            }
+            if !self.history.is_empty() {
+                content = content.push(button("↩️ Undo last action").on_press(Message::Undo).padding(10));
+            }
             if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
             if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
 
@@ -299,106 +1825,1145 @@ impl TheatreApp {
         let mut content = column![
             text("Cancel Booking").size(36),
             text_input("Enter Booking ID", &self.booking_id_input).on_input(Message::BookingIdChanged).padding(10),
-            button("❌ Cancel Booking").on_press(Message::CancelBookingConfirm).padding(15),
+            button("❌ Cancel Booking").on_press(Message::RequestCancel).padding(15),
             button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
         ].spacing(15).align_items(Alignment::Center);
 
+        if let Some(booking) = self.pending_cancel.as_ref().and_then(|id| self.bookings.iter().find(|b| &b.id == id)) {
+            let show = self.shows.get(booking.show_id).map_or("Unknown show", |s| s.name.as_str());
+            let pass_note = if booking.pass_id.is_some() { "⚠️ Part of a season pass: all linked bookings will be cancelled" } else { "" };
+            content = content.push(container(column![
+                text(format!("👤 {}", booking.name_with_ref())).size(18),
+                text(format!("🎬 {} | 💺 {}", show, booking.seat_list())).size(16),
+                text(format!("💰 {} | Refund if cancelled now: {}", self.format_price(booking.grand_total), self.format_price(self.refund_for(booking)))).size(16),
+                text(pass_note).size(14),
+                button("⚠️ Confirm Cancellation").on_press(Message::CancelBookingConfirm).padding(10),
+            ].spacing(8).padding(15)).style(container_card_style));
+        }
+
+        if !self.history.is_empty() {
+            content = content.push(button("↩️ Undo last action").on_press(Message::Undo).padding(10));
+        }
+        if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
+        if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
+        content.into()
+    }
+
+    fn modify_booking_view(&self) -> Element<'_, Message> {
+        let mut content = column![
+            text("Change Seats").size(36),
+            row![
+                text_input("Enter Booking ID", &self.modify_id_input).on_input(Message::ModifyIdChanged).on_submit(Message::LoadBookingForModify).padding(10),
+                button("Find").on_press(Message::LoadBookingForModify).padding(10),
+            ].spacing(10),
+        ].spacing(10).align_items(Alignment::Center);
+
+        let loaded = self.modifying.as_ref().and_then(|(id, selection)| {
+            let booking = self.bookings.iter().find(|b| &b.id == id)?;
+            Some((booking, selection, self.shows.get(booking.show_id)?, self.seats.get(booking.show_id)?))
+        });
+        if let Some((booking, selection, show, grid)) = loaded {
+            let seat_count = grid.len() * grid.first().map_or(0, Vec::len);
+            let seat_grid = if seat_count > MAX_GRID_RENDER_SEATS {
+                self.seat_code_entry(grid, selection)
+            } else {
+                // Seats held by this booking are drawn as free so they can be kept or released
+                screen_and_seat_grid(grid.iter().enumerate().fold(column![].spacing(10).align_items(Alignment::Center), |col, (r, seats)| {
+                    let label = seats.first().map_or(String::new(), |s| s.row.to_string());
+                    col.push(seats.iter().enumerate().fold(row![text(label).size(16).width(20)].spacing(8).align_items(Alignment::Center), |line, (c, seat)| {
+                        let seat = Seat { is_booked: seat.is_booked && seat.booking_id.as_ref() != Some(&booking.id), ..seat.clone() };
+                        let line = line.push(create_seat_button(&seat, if selection.contains(&(r, c)) { SeatHighlight::Selected } else { SeatHighlight::None }, r, c, &self.palette, self.prefs.seat_mode, Message::ModifySelectSeat));
+                        if show.aisle_after.contains(&(c + 1)) && c + 1 < seats.len() { line.push(Space::with_width(24)) } else { line }
+                    }))
+                }))
+            };
+            let (gross, net) = self.compute_total(show.id, selection);
+            let new_price = if booking.pass_id.is_some() { gross * (1.0 - SEASON_PASS_DISCOUNT) } else { net };
+            content = content
+                .push(text(format!("👤 {} | 🎬 {} | Current seats: {}", booking.name_with_ref(), show.name, booking.seat_list())).size(16))
+                .push(seat_grid)
+                .push(text(format!("New price: {} (was {})", self.format_price(new_price), self.format_price(booking.price))).size(16))
+                .push(button("✅ Confirm Seat Change").on_press(Message::ConfirmModify).padding(15));
+        }
+
+        content = content.push(button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10));
+        if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
+        if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
+        content.into()
+    }
+
+    fn customer_history_view(&self) -> Element<'_, Message> {
+        let mut content = column![
+            text("Customer History").size(36),
+            row![
+                text_input("Customer name", &self.customer_history_input).on_input(Message::CustomerHistoryChanged).on_submit(Message::SearchCustomerHistory).padding(10),
+                button("Search").on_press(Message::SearchCustomerHistory).padding(10),
+            ].spacing(10),
+        ].spacing(10).align_items(Alignment::Center);
+
+        if let Some(name) = &self.customer_history_query {
+            let bookings = self.customer_bookings(name);
+            if bookings.is_empty() {
+                content = content.push(text("No bookings found for this customer").size(18));
+            } else {
+                let list = bookings.iter().fold(column![].spacing(8), |col, booking| {
+                    let show = self.shows.get(booking.show_id).map_or("Unknown show", |s| s.name.as_str());
+                    col.push(container(column![
+                        text(format!("🎬 {} | 💺 {} | 💰 {}", show, booking.seat_list(), self.format_price(booking.grand_total))).size(16),
+                        text(format!("🕒 {} | ID: {}", booking.booking_time, booking.id)).size(14),
+                    ].spacing(4)).padding(10).width(Length::Fill).style(container_card_style))
+                });
+                let total = self.customer_total(name);
+                content = content
+                    .push(text(format!("{} booking(s) for {}", bookings.len(), bookings[0].customer_name)).size(18))
+                    .push(scrollable(list).height(Length::Fill))
+                    .push(text(format!("Total spent: {}", self.format_price(total))).size(20));
+            }
+        }
+
+        content = content.push(button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10));
+        if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
+        content.into()
+    }
+
+    fn add_show_view(&self) -> Element<'_, Message> {
+        let form = &self.show_form;
+        let mut content = column![
+            text("Add Show").size(36),
+            text_input("Movie name", &form.name).on_input(|v| Message::ShowFormChanged(ShowField::Name, v)).padding(10),
+            text_input("Date (DD-MM-YYYY)", &form.date).on_input(|v| Message::ShowFormChanged(ShowField::Date, v)).padding(10),
+            text_input("Time (HH:MM)", &form.time).on_input(|v| Message::ShowFormChanged(ShowField::Time, v)).padding(10),
+            text_input("Hall", &form.hall).on_input(|v| Message::ShowFormChanged(ShowField::Hall, v)).padding(10),
+            text_input("Ticket price", &form.price).on_input(|v| Message::ShowFormChanged(ShowField::Price, v)).padding(10),
+            row![
+                text_input("Rows (default 4)", &form.rows).on_input(|v| Message::ShowFormChanged(ShowField::Rows, v)).padding(10),
+                text_input("Seats per row (default 5)", &form.cols).on_input(|v| Message::ShowFormChanged(ShowField::Cols, v)).padding(10),
+            ].spacing(10),
+            button("➕ Create Show").on_press(Message::CreateShow).padding(15),
+            button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
+        ].spacing(10).align_items(Alignment::Center);
+
+        if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
+        if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
+        content.into()
+    }
+
+    fn season_pass_view(&self) -> Element<'_, Message> {
+        let shows = if self.shows.is_empty() { column![text("No shows available")] } else { column![] };
+        let shows = self.shows.iter().fold(shows.spacing(8), |col, show| {
+            let id = show.id;
+            let label = format!("{} — {} {} ({})", show.name, show.display_date(self.prefs.date_format), show.time, show.hall);
+            col.push(checkbox(label, self.pass_shows.contains(&id)).on_toggle(move |_| Message::TogglePassShow(id)))
+        });
+        let tier_factor = parse_seat_code(&self.pass_seat_input).map_or(1.0, |(row, _)| SeatTier::for_row(row).factor());
+        let gross: f64 = self.pass_shows.iter().filter_map(|&id| self.shows.get(id)).map(|show| show.effective_price() * tier_factor).sum();
+
+        let mut content = column![
+            text("Season Pass").size(36),
+            text(format!("Book the same seat for several shows and save {:.0}%", SEASON_PASS_DISCOUNT * 100.0)).size(16),
+            shows,
+            text_input("Seat (e.g. B3)", &self.pass_seat_input).on_input(Message::PassSeatChanged).padding(10),
+            text_input("Enter your name", &self.customer_name).on_input(Message::CustomerNameChanged).padding(10),
+            text(format!("Total: {} (was {})", self.format_price(gross * (1.0 - SEASON_PASS_DISCOUNT)), self.format_price(gross))).size(16),
+            button("🎟️ Confirm Season Pass").on_press(Message::ConfirmSeasonPass).padding(15),
+            button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
+        ].spacing(10).align_items(Alignment::Center);
+
         if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
         if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
         content.into()
     }
 
     fn view_seats(&self) -> Element<'_, Message> {
-        column![
+        let picker = self.shows.iter().fold(row![].spacing(8), |picker, show| {
+            let label = if self.selected_show == Some(show.id) { format!("✔ {}", show.name) } else { show.name.clone() };
+            picker.push(button(text(label).size(14)).on_press(Message::PreviewShowSeats(show.id)).padding(6))
+        });
+
+        let mut content = column![
             text("Seat Availability").size(36),
-            button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
-        ].spacing(10).align_items(Alignment::Center).into()
+            scrollable(picker).direction(scrollable::Direction::Horizontal(scrollable::Properties::default())),
+        ].spacing(10).align_items(Alignment::Center);
+
+        match self.selected_show.and_then(|id| Some((self.shows.get(id)?, self.seats.get(id)?))) {
+            Some((show, grid)) => {
+                let booked = grid.iter().flatten().filter(|s| s.is_booked).count();
+                let total = grid.iter().flatten().filter(|s| !s.is_reserved).count();
+                // Heat map ranks this show's sold seats by sales order: red sold first, yellow sold last
+                let ranked = self.fastest_selling_seats(show.id, booked);
+                let seat_count = grid.len() * grid.first().map_or(0, Vec::len);
+                let seat_map: Element<_> = if seat_count > MAX_GRID_RENDER_SEATS {
+                    text(format!("⚠️ This hall has {} seats, too many to draw", seat_count)).style(Color::from_rgb(0.95, 0.7, 0.2)).into()
+                } else {
+                    screen_and_seat_grid(grid.iter().fold(column![].spacing(10).align_items(Alignment::Center), |map, seats| {
+                        let label = seats.first().map_or(String::new(), |s| s.row.to_string());
+                        let line = seats.iter().fold(row![text(label).size(16).width(20)].spacing(8).align_items(Alignment::Center), |line, seat| {
+                            let code = format!("{}{}", seat.row, seat.col);
+                            let cell = match ranked.iter().position(|c| *c == code) {
+                                Some(rank) if self.seat_popularity => {
+                                    let heat = popularity_color(rank, ranked.len());
+                                    container(text(code).size(14)).padding(10).style(move |_: &Theme| container::Appearance {
+                                        background: Some(heat.into()),
+                                        text_color: Some(Color::BLACK),
+                                        border: iced::Border { radius: 4.0.into(), ..Default::default() },
+                                        ..Default::default()
+                                    })
+                                }
+                                _ => container(text(if seat.is_reserved { "♿" } else if seat.is_booked { "🔴" } else { "🟢" }).size(24)).padding(8),
+                            };
+                            let line = line.push(cell);
+                            if show.aisle_after.contains(&seat.col) && seat.col < seats.len() { line.push(Space::with_width(24)) } else { line }
+                        });
+                        map.push(line)
+                    }))
+                };
+                content = content
+                    .push(text(format!("{} — {} {} | {}", show.name, show.display_date(self.prefs.date_format), show.time, show.hall)).size(20))
+                    .push(text(format!("🔴 {} booked | 🟢 {} available | {} total", booked, total - booked, total)).size(16))
+                    .push(checkbox("🔥 Popularity (sales order)", self.seat_popularity).on_toggle(Message::ToggleSeatPopularity))
+                    .push(seat_map)
+                    .push(text(if self.seat_popularity { "Legend: red sold first → yellow sold last" } else { "Legend: 🟢 Available  🔴 Booked  ♿ Reserved" }).size(14));
+                if self.seat_popularity && !ranked.is_empty() {
+                    content = content.push(text(format!("First to sell: {}", ranked.iter().take(5).cloned().collect::<Vec<_>>().join(", "))).size(14));
+                }
+            }
+            None => content = content.push(text("Pick a show to see its seat map").size(16)),
+        }
+
+        content.push(button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)).into()
     }
 
     fn records_view(&self) -> Element<'_, Message> {
+        // Case-insensitive match on customer name or booking id, on top of the group filter
+        let query = self.records_filter.trim().to_lowercase();
+        let matching: Vec<&Booking> = self.bookings.iter().rev()
+            .filter(|b| self.records_group_filter.is_none() || b.group_name == self.records_group_filter)
+            .filter(|b| query.is_empty() || b.customer_name.to_lowercase().contains(&query) || b.id.to_lowercase().contains(&query))
+            .collect();
         let records: Element<_> = if self.bookings.is_empty() {
             text("No bookings yet").into()
+        } else if matching.is_empty() {
+            text("No matching records").into()
         } else {
-            self.bookings.iter().rev().fold(column![].spacing(10), |col, b| {
+            matching.into_iter()
+                .fold(column![].spacing(10), |col, b| {
+                let attendance: Element<_> = if let Some(expires) = &b.expires_at {
+                    row![
+                        text(format!("⏳ Awaiting payment until {}", expires)).size(14),
+                        button("💵 Finalize Payment").on_press(Message::FinalizeBooking(b.id.clone())).padding(5),
+                    ].spacing(10).align_items(Alignment::Center).into()
+                } else if b.attended {
+                    text("✅ Attended").size(14).into()
+                } else if b.no_show {
+                    text("🚫 No-show (seat released)").size(14).into()
+                } else {
+                    button("Mark Attended").on_press(Message::MarkAttended(b.id.clone())).padding(5).into()
+                };
+                let actions = row![attendance, button("✉️ Preview Email").on_press(Message::PreviewEmail(b.id.clone())).padding(5)].spacing(10);
+                let seat = if b.overbooked { "⚠️ OVERBOOKED (no seat)".to_string() } else { b.seat_list() };
                 col.push(container(column![
-                    text(format!("🎫 ID: {}", b.id)).size(14),
-                    text(format!("👤 {}", b.customer_name)).size(16),
-                    text(format!("🎬 {} | 💺 {}", self.shows[b.show_id].name, b.seat)).size(14),
-                ].padding(15)).style(container_card_style).width(Length::Fill))
+                    text(format!("🎫 ID: {} | 🧾 {}", b.id, b.receipt_no)).size(14),
+                    text(match &b.group_name {
+                        Some(group) => format!("👤 {} | 🏢 {}", b.name_with_ref(), group),
+                        None => format!("👤 {}", b.name_with_ref()),
+                    } + &b.phone.as_ref().map_or(String::new(), |phone| format!(" | 📞 {}", phone))).size(16),
+                    text(format!("🎬 {} | 💺 {}", self.shows.get(b.show_id).map_or("Unknown show", |s| s.name.as_str()), seat)).size(14),
+                    text(if b.gross_price > b.price {
+                        format!("💰 {} (gross {}, discount {})", self.format_price(b.price), self.format_price(b.gross_price), self.format_price(b.gross_price - b.price))
+                    } else {
+                        format!("💰 {}", self.format_price(b.price))
+                    }).size(14),
+                    actions,
+                ].spacing(5).padding(15)).style(container_card_style).width(Length::Fill))
             }).into()
         };
 
-        column![
+        let mut groups: Vec<&String> = self.bookings.iter().filter_map(|b| b.group_name.as_ref()).collect();
+        groups.sort();
+        groups.dedup();
+        let group_bar = groups.into_iter().fold(
+            row![button("All").on_press(Message::FilterRecordsByGroup(None)).padding(6)].spacing(8),
+            |bar, group| {
+                let label = if self.records_group_filter.as_ref() == Some(group) { format!("✔ {}", group) } else { group.clone() };
+                bar.push(button(text(label).size(14)).on_press(Message::FilterRecordsByGroup(Some(group.clone()))).padding(6))
+            },
+        );
+
+        let seat_lookup: Element<_> = if self.seat_lookup_input.trim().is_empty() {
+            Space::with_height(0).into()
+        } else {
+            match self.lookup_seat(&self.seat_lookup_input) {
+                Ok(lines) => lines.into_iter().fold(column![].spacing(4), |col, line| col.push(text(line).size(14))).into(),
+                Err(e) => text(e).size(14).style(Color::from_rgb(0.9, 0.3, 0.3)).into(),
+            }
+        };
+
+        let records: Element<_> = match &self.email_preview {
+            Some(body) => container(column![
+                text(body).size(14),
+                button("Close Preview").on_press(Message::CloseEmailPreview).padding(8),
+            ].spacing(10).padding(15)).style(container_card_style).width(Length::Fill).into(),
+            None => records,
+        };
+
+        let mut content = column![
             text("All Booking Records").size(36),
-            button("💾 Export Records").on_press(Message::ExportRecords).padding(10),
+            row![
+                button("💾 Export Records").on_press(Message::ExportRecords).padding(10),
+                button("📄 Export CSV").on_press(Message::ExportCsv).padding(10),
+                button("🖨 Print All Tickets").on_press(Message::PrintAllTickets).padding(10),
+                button("📥 Import Records").on_press(Message::ImportRecords).padding(10),
+            ].spacing(10),
+            text_input("Search by customer name or booking ID", &self.records_filter).on_input(Message::RecordsFilterChanged).padding(10),
+            text_input("Find booking by seat, e.g. Hall 1 / A3", &self.seat_lookup_input).on_input(Message::SeatLookupChanged).padding(10),
+            seat_lookup,
+            group_bar,
             scrollable(records),
+            row![
+                text_input("Type RESET to clear all bookings", &self.reset_confirm_input).on_input(Message::ResetConfirmChanged).padding(10).width(300),
+                button("🗑️ Clear All Bookings").on_press(Message::ClearAllBookings).padding(10),
+            ].spacing(10),
             button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
-        ].spacing(10).into()
+        ].spacing(10);
+
+        if let Some(msg) = &self.error_message { content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3))); }
+        if let Some(msg) = &self.success_message { content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3))); }
+        content.into()
     }
 
     fn statistics_view(&self) -> Element<'_, Message> {
-        let total_bookings = self.bookings.len().to_string();
-        let total_revenue = format!("LKR {:.2}", self.bookings.iter().map(|b| b.price).sum::<f64>());
-        let available_seats = self.shows.iter().map(|s| s.available_seats).sum::<usize>().to_string();
+        let stats = &self.stats_cache;
+        let total_bookings = stats.total_bookings.to_string();
+        let total_revenue = self.format_price(stats.total_revenue);
+        let available_seats = stats.available_seats.to_string();
+        let (booked, capacity) = stats.occupancy.iter().fold((0, 0), |(b, c), &(sb, sc)| (b + sb, c + sc));
+        let occupancy = format!("{:.0}%", occupancy_pct(booked, capacity));
 
-        column![
+        let mut by_show: Vec<(&Show, usize, usize)> = self.shows.iter()
+            .filter_map(|show| stats.occupancy.get(show.id).map(|&(b, c)| (show, b, c)))
+            .collect();
+        by_show.sort_by(|a, b| occupancy_pct(b.1, b.2).total_cmp(&occupancy_pct(a.1, a.2)));
+        let per_show = by_show.into_iter().fold(column![text("Occupancy by show").size(20)].spacing(5).align_items(Alignment::Center), |col, (show, b, c)| {
+            col.push(text(format!("🎬 {}: {}/{} seats ({:.0}%)", show.name, b, c, occupancy_pct(b, c))).size(16))
+        });
+
+        let bars: Vec<(String, f64)> = self.shows.iter()
+            .map(|show| (show.name.clone(), stats.revenue_by_show.get(show.id).copied().unwrap_or(0.0)))
+            .collect();
+        let revenue_chart: Element<_> = if bars.iter().all(|(_, revenue)| *revenue <= 0.0) {
+            text("No data yet").size(16).into()
+        } else {
+            let width = (bars.len() as f32 * RevenueChart::SLOT_WIDTH).max(300.0);
+            canvas(RevenueChart { bars }).width(width).height(260).into()
+        };
+        let revenue_chart = column![text("Revenue by show").size(20), revenue_chart].spacing(5).align_items(Alignment::Center);
+
+        let activity = stats.recent_activity.iter().fold(column![text("Recent activity").size(20)].spacing(5).align_items(Alignment::Center), |col, entry| {
+            let who = self.bookings.iter().find(|b| b.id == entry.booking_id).map_or_else(|| entry.booking_id.clone(), Booking::name_with_ref);
+            col.push(text(format!("🕒 {} | {} | {} | {}", entry.timestamp, entry.action, who, entry.seats.join(", "))).size(14))
+        });
+
+        let group_revenue = stats.revenue_by_group.iter().fold(column![].spacing(5).align_items(Alignment::Center), |col, (group, revenue)| {
+            col.push(text(format!("🏢 {}: {}", group, self.format_price(*revenue))).size(16))
+        });
+
+        let cards = if stats.total_bookings == 0 {
+            column![
+                text("No statistics yet — figures appear after the first booking").size(18),
+                stat_card("💺 Available Seats", available_seats),
+                per_show,
+            ]
+        } else {
+            column![
+                stat_card("📊 Total Bookings", total_bookings),
+                stat_card("💰 Total Revenue", total_revenue),
+                stat_card("💺 Available Seats", available_seats),
+                stat_card("🎭 Occupancy", occupancy),
+                revenue_chart,
+                per_show,
+                group_revenue,
+                activity,
+            ]
+        };
+
+        let mut content = column![
             text("Booking Statistics").size(36),
-            Space::with_height(20),
-            stat_card("📊 Total Bookings", total_bookings),
-            stat_card("💰 Total Revenue", total_revenue),
-            stat_card("💺 Available Seats", available_seats),
-            Space::with_height(20),
-            button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10)
-        ].spacing(10).align_items(Alignment::Center).into()
-    }
+            button("📄 Export Report").on_press(Message::ExportStats).padding(10),
+        ].spacing(10).align_items(Alignment::Center);
+        if let Some(msg) = &self.error_message {
+            content = content.push(text(msg).style(Color::from_rgb(0.9, 0.3, 0.3)));
+        }
+        if let Some(msg) = &self.success_message {
+            content = content.push(text(msg).style(Color::from_rgb(0.3, 0.9, 0.3)));
+        }
 
-    fn save_ticket(&self, booking: &Booking) {
-        let show = &self.shows[booking.show_id];
-        let content = format!("Movie: {}\nSeat: {}\nPrice: LKR {:.2}\nID: {}", show.name, booking.seat, booking.price, booking.id);
-        let _ = fs::write(format!("ticket_{}.txt", booking.id), content);
+        content
+            .push(Space::with_height(20))
+            .push(scrollable(cards.spacing(10).align_items(Alignment::Center).width(Length::Fill)).height(Length::Fill))
+            .push(Space::with_height(20))
+            .push(button("← Back to Home").on_press(Message::ChangeView(View::Home)).padding(10))
+            .into()
     }
 
-    fn export_records(&self) {
-        if let Ok(json) = serde_json::to_string_pretty(&self.bookings) {
-            let _ = fs::write("bookings_export.json", json);
+    // Frees seats of bookings nobody turned up for once the grace period after showtime has passed.
+    // Bookings sold after that cutoff are left alone so late walk-in sales aren't released immediately.
+    fn release_no_shows(&mut self) {
+        let now = Local::now().naive_local();
+        let mut released = false;
+        for booking in self.bookings.iter_mut().filter(|b| !b.attended && !b.no_show && b.expires_at.is_none()) {
+            let (Some(show), Some(seats)) = (self.shows.get_mut(booking.show_id), self.seats.get_mut(booking.show_id)) else { continue };
+            let Some(cutoff) = show.starts_at().map(|t| t + Duration::minutes(self.prefs.no_show_release_minutes)) else { continue };
+            let booked_at = NaiveDateTime::parse_from_str(&booking.booking_time, "%d-%m-%Y %H:%M:%S").ok();
+            if now < cutoff || booked_at.is_none_or(|t| t >= cutoff) {
+                continue;
+            }
+            booking.no_show = true;
+            show.available_seats += free_booking_seats(seats, &booking.id).len();
+            append_audit("no_show_release", booking, None);
+            released = true;
+        }
+        if released {
+            self.refresh_stats();
         }
     }
-}
 
-// ============================================================================
-// Styles and Helpers
-// ============================================================================
+    // Drops kiosk reservations whose pay-at-counter window has passed and frees their seats
+    fn expire_kiosk_reservations(&mut self) {
+        let now = Local::now().naive_local();
+        let expired: Vec<Booking> = self.bookings.iter()
+            .filter(|b| b.expires_at.as_deref()
+                .and_then(|t| NaiveDateTime::parse_from_str(t, "%d-%m-%Y %H:%M:%S").ok())
+                .is_some_and(|t| t <= now))
+            .cloned()
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+        for booking in &expired {
+            if let (Some(show), Some(seats)) = (self.shows.get_mut(booking.show_id), self.seats.get_mut(booking.show_id)) {
+                show.available_seats += free_booking_seats(seats, &booking.id).len();
+            }
+            append_audit("reservation_expired", booking, None);
+        }
+        self.bookings.retain(|b| !expired.iter().any(|e| e.id == b.id));
+        self.refresh_stats();
+    }
 
-fn container_dark_style(_theme: &Theme) -> container::Appearance {
-    container::Appearance { background: Some(Color::from_rgb(0.05, 0.05, 0.1).into()), ..Default::default() }
-}
+    fn refresh_stats(&mut self) {
+        self.stats_cache = self.compute_stats();
+    }
 
-fn container_card_style(_theme: &Theme) -> container::Appearance {
+    // Shared by the Statistics view (through the cache) and the exported report, so both agree
+    fn compute_stats(&self) -> StatsCache {
+        StatsCache {
+            total_bookings: self.bookings.len(),
+            total_revenue: self.bookings.iter().map(|b| b.price).sum(),
+            available_seats: self.shows.iter().map(|s| s.available_seats).sum(),
+            occupancy: self.seats.iter().map(|rows| {
+                let seats = rows.iter().flatten();
+                (seats.clone().filter(|s| s.is_booked).count(), seats.filter(|s| !s.is_reserved).count())
+            }).collect(),
+            revenue_by_group: self.bookings.iter()
+                .filter_map(|b| Some((b.group_name.clone()?, b.price)))
+                .fold(Vec::<(String, f64)>::new(), |mut groups, (group, price)| {
+                    match groups.iter_mut().find(|(g, _)| *g == group) {
+                        Some((_, total)) => *total += price,
+                        None => groups.push((group, price)),
+                    }
+                    groups
+                }),
+            recent_activity: read_audit().into_iter().rev().take(5).collect(),
+            revenue_by_show: self.shows.iter()
+                .map(|show| self.bookings.iter().filter(|b| b.show_id == show.id).map(|b| b.price).sum())
+                .collect(),
+        }
+    }
+
+    fn export_stats(&self) -> io::Result<()> {
+        let stats = self.compute_stats();
+        let (booked, capacity) = stats.occupancy.iter().fold((0, 0), |(b, c), &(sb, sc)| (b + sb, c + sc));
+        let mut report = format!(
+            "{} statistics report\nGenerated: {}\n\nTotal bookings: {}\nTotal revenue: {}\nAvailable seats: {}\nOccupancy: {:.0}%\n\nBy show:\n",
+            VENUE_NAME, Local::now().format("%d-%m-%Y %H:%M:%S"), stats.total_bookings, self.format_price(stats.total_revenue),
+            stats.available_seats, occupancy_pct(booked, capacity),
+        );
+        for show in &self.shows {
+            let (b, c) = stats.occupancy.get(show.id).copied().unwrap_or((0, 0));
+            let revenue = stats.revenue_by_show.get(show.id).copied().unwrap_or(0.0);
+            report.push_str(&format!("  {}: {}/{} seats ({:.0}%), revenue {}\n", show.name, b, c, occupancy_pct(b, c), self.format_price(revenue)));
+        }
+        write_file_atomic("statistics_report.txt", report)
+    }
+
+    // The service charge is refunded on the same terms as the ticket price. An unpaid kiosk
+    // reservation took no money, so there is nothing to pay back.
+    fn refund_for(&self, booking: &Booking) -> f64 {
+        if booking.expires_at.is_some() { return 0.0; }
+        refund_amount(booking.grand_total, self.show_datetime(booking.show_id), Local::now().naive_local())
+    }
+
+    // None for unknown shows or a date/time that doesn't parse as DD-MM-YYYY HH:MM
+    fn show_datetime(&self, show_id: usize) -> Option<NaiveDateTime> {
+        self.shows.get(show_id)?.starts_at()
+    }
+
+    // No free seat and no overbooking allowance left
+    fn is_sold_out(&self, show_id: usize) -> bool {
+        self.shows.get(show_id).is_none_or(|show| show.available_seats == 0 && self.overbook_remaining(show_id) == 0)
+    }
+
+    // True only for the booking that took occupancy from below CAPACITY_ALERT_PCT to at or above it
+    fn crossed_capacity_alert(&self, show_id: usize, available_before: usize) -> bool {
+        let capacity = self.seats.get(show_id).map_or(0, |grid| sellable_seats(grid));
+        let occupancy = |available: usize| occupancy_pct(capacity.saturating_sub(available), capacity);
+        occupancy(available_before) < CAPACITY_ALERT_PCT && occupancy(self.shows[show_id].available_seats) >= CAPACITY_ALERT_PCT
+    }
+
+    // Logical places sold beyond physical capacity, allowed up to the show's overbook percentage
+    fn overbook_remaining(&self, show_id: usize) -> usize {
+        let (Some(show), Some(grid)) = (self.shows.get(show_id), self.seats.get(show_id)) else { return 0 };
+        let allowance = (sellable_seats(grid) as f32 * show.overbook_pct / 100.0).floor() as usize;
+        let used = self.bookings.iter().filter(|b| b.show_id == show_id && b.overbooked).count();
+        allowance.saturating_sub(used)
+    }
+
+    fn format_price(&self, amount: f64) -> String {
+        self.prefs.price_format.format(amount)
+    }
+
+    // Restores bookings from the last export so reservations survive a restart. A missing or
+    // malformed file starts fresh, and records for unknown shows or clashing seats are skipped.
+    fn load_state(&mut self) {
+        let _ = self.import_records("bookings_export.json");
+    }
+
+    // Merges bookings from an exported file, rebuilding seat state. Returns (imported, skipped);
+    // skipped records are for unknown shows, already-known ids, or seats that are taken or don't exist.
+    fn import_records(&mut self, path: impl AsRef<Path>) -> io::Result<(usize, usize)> {
+        let json = fs::read_to_string(path)?;
+        let bookings: Vec<Booking> = serde_json::from_str(&json)?;
+        let total = bookings.len();
+        let mut imported = 0;
+        for mut booking in bookings {
+            // Records from before service charges were added only have the ticket price
+            if booking.grand_total < booking.price {
+                booking.grand_total = booking.price + booking.service_charge;
+            }
+            if booking.show_id >= self.shows.len() || self.bookings.iter().any(|b| b.id == booking.id) {
+                continue;
+            }
+            if !booking.overbooked && !booking.no_show {
+                let grid = &self.seats[booking.show_id];
+                let positions: Option<Vec<(usize, usize)>> = booking.seats.iter()
+                    .map(|code| parse_seat_code(code).filter(|&(r, c)| grid.get(r).and_then(|row| row.get(c)).is_some_and(Seat::is_bookable)))
+                    .collect();
+                let Some(positions) = positions.filter(|p| !p.is_empty() && p.iter().enumerate().all(|(i, s)| !p[..i].contains(s))) else { continue };
+                // Bookings are stored oldest first, so import order reproduces the sales order
+                for &(row, col) in &positions {
+                    let order = self.next_booking_order();
+                    let seat = &mut self.seats[booking.show_id][row][col];
+                    seat.is_booked = true;
+                    seat.booking_id = Some(booking.id.clone());
+                    seat.booking_order = Some(order);
+                }
+                self.shows[booking.show_id].available_seats -= positions.len();
+            }
+            self.bookings.push(booking);
+            imported += 1;
+        }
+        Ok((imported, total - imported))
+    }
+
+    fn save_preferences(&mut self) {
+        if let Err(e) = self.prefs.save() {
+            self.error_message = Some(format!("Preferences could not be saved: {}", e));
+        }
+    }
+
+    // Scanner entry point: a ticket is valid while its booking id is on record
+    fn verify_ticket(&self, id: &str) -> bool {
+        self.find_booking(id).is_some()
+    }
+
+    // Codes are typed or pasted in by staff, so matching ignores case, whitespace and an "ID:" prefix
+    fn find_booking(&self, id: &str) -> Option<&Booking> {
+        let id = normalize_booking_id(id);
+        self.bookings.iter().find(|b| b.id.eq_ignore_ascii_case(&id))
+    }
+
+    fn new_booking_code(&self) -> String {
+        self.first_unused_code(std::iter::repeat_with(generate_booking_code))
+    }
+
+    // Candidates are drawn until one doesn't clash with an existing booking
+    fn first_unused_code(&self, candidates: impl IntoIterator<Item = String>) -> String {
+        candidates.into_iter().find(|code| self.find_booking(code).is_none()).expect("booking code candidates ran out")
+    }
+
+    // Writes a printable HTML ticket, falling back to the plain-text ticket if that fails.
+    // A missing QR code never blocks the booking; the ticket is just printed without it.
+    fn save_ticket(&self, booking: &Booking) -> io::Result<()> {
+        let qr_file = format!("ticket_{}_qr.png", booking.id);
+        let qr = save_qr_code(&booking.id, &qr_file).ok().map(|_| qr_file.as_str());
+        if let Some(show) = self.shows.get(booking.show_id) {
+            let html = build_ticket_html(booking, show, &self.prefs.price_format, qr);
+            if write_file_atomic(format!("ticket_{}.html", booking.id), html).is_ok() {
+                return Ok(());
+            }
+        }
+        write_file_atomic(format!("ticket_{}.txt", booking.id), self.ticket_text(booking))
+    }
+
+    fn ticket_text(&self, booking: &Booking) -> String {
+        let movie = self.shows.get(booking.show_id).map_or("Unknown show", |s| s.name.as_str());
+        let mut content = format!("Movie: {}\nSeats: {}\nPrice: {}\nReceipt: {}\nID: {}", movie, booking.seat_list(), self.format_price(booking.price), booking.receipt_no, booking.id);
+        if let Some(phone) = &booking.phone {
+            content.push_str(&format!("\nPhone: {}", phone));
+        }
+        if booking.service_charge > 0.0 {
+            content.push_str(&format!("\nService charge: {}\nTotal: {}", self.format_price(booking.service_charge), self.format_price(booking.grand_total)));
+        }
+        if let (Some(tendered), Some(change)) = (booking.tendered, booking.change) {
+            content.push_str(&format!("\nTendered: {}\nChange: {}", self.format_price(tendered), self.format_price(change)));
+        }
+        content
+    }
+
+    // Regenerates every ticket file (e.g. after an import) plus one combined file for batch
+    // printing, with a form feed between tickets so each lands on its own page. Unpaid kiosk
+    // reservations get their payment slip instead and stay out of the combined file.
+    fn export_all_tickets(&self) -> io::Result<(usize, usize)> {
+        let (unpaid, paid): (Vec<&Booking>, Vec<&Booking>) = self.bookings.iter().partition(|b| b.expires_at.is_some());
+        let failed = paid.iter().filter(|b| self.save_ticket(b).is_err()).count()
+            + unpaid.iter().filter(|b| self.save_payment_slip(b).is_err()).count();
+        let combined: Vec<String> = paid.iter().map(|b| self.ticket_text(b)).collect();
+        write_file_atomic("all_tickets.txt", combined.join("\n\x0c\n"))?;
+        Ok((paid.len(), failed))
+    }
+
+    fn save_payment_slip(&self, booking: &Booking) -> io::Result<()> {
+        let movie = self.shows.get(booking.show_id).map_or("Unknown show", |s| s.name.as_str());
+        let content = format!(
+            "PAY AT COUNTER\n\nMovie: {}\nSeats: {}\nAmount due: {}\nReserved until: {}\nID: {}",
+            movie, booking.seat_list(), self.format_price(booking.grand_total), booking.expires_at.as_deref().unwrap_or("-"), booking.id
+        );
+        write_file_atomic(format!("slip_{}.txt", booking.id), content)
+    }
+
+    fn save_shows(&self) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.shows)?;
+        write_file_atomic("shows.json", json)
+    }
+
+    // End-of-day reset: backs up the bookings, then frees every seat and empties the booking list.
+    // Nothing is cleared if the backup can't be written. Returns the backup file name.
+    fn clear_all_bookings(&mut self) -> io::Result<String> {
+        let backup = format!("bookings_backup_{}.json", Local::now().format("%Y%m%d_%H%M%S"));
+        write_file_atomic(&backup, serde_json::to_string_pretty(&self.bookings)?)?;
+
+        let cleared = self.bookings.len();
+        self.bookings.clear();
+        for (show, grid) in self.shows.iter_mut().zip(self.seats.iter_mut()) {
+            for seat in grid.iter_mut().flatten() {
+                seat.is_booked = false;
+                seat.booking_id = None;
+                seat.booking_order = None;
+            }
+            show.available_seats = sellable_seats(grid);
+        }
+        self.selected_seats.clear();
+        self.history.clear();
+        self.last_booked.clear();
+        self.pending_cancel = None;
+        self.modifying = None;
+        self.reset_confirm_input.clear();
+        append_audit_entry(&AuditEntry {
+            action: format!("clear_all ({} bookings)", cleared),
+            booking_id: String::new(),
+            seats: Vec::new(),
+            timestamp: Local::now().to_rfc3339(),
+            refund: None,
+        });
+        self.refresh_stats();
+        // The export is what gets reloaded on startup, so it must not bring the old bookings back
+        self.export_records()?;
+        Ok(backup)
+    }
+
+    fn export_records(&mut self) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.bookings)?;
+        write_file_atomic("bookings_export.json", &json)?;
+        self.last_saved = json;
+        Ok(())
+    }
+
+    fn auto_save(&mut self) {
+        let Ok(json) = serde_json::to_string_pretty(&self.bookings) else { return };
+        if json == self.last_saved {
+            return;
+        }
+        if let Err(e) = self.export_records() {
+            self.error_message = Some(format!("Auto-save failed: {}", e));
+        }
+    }
+
+    fn export_records_csv(&self) -> io::Result<()> {
+        let mut csv = String::from("id,customer_name,phone,show_name,seat,booking_time,price\n");
+        for b in &self.bookings {
+            let show = self.shows.get(b.show_id).map_or("Unknown show", |s| s.name.as_str());
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{:.2}\n",
+                csv_field(&b.id), csv_field(&b.customer_name), csv_field(b.phone.as_deref().unwrap_or("")), csv_field(show), csv_field(&b.seat_list()), csv_field(&b.booking_time), b.price
+            ));
+        }
+        write_file_atomic("bookings_export.csv", csv)
+    }
+}
+
+// ============================================================================
+// Styles and Helpers
+// ============================================================================
+
+// Red for the first seat sold, shading to yellow for the last
+fn popularity_color(rank: usize, count: usize) -> Color {
+    let t = if count > 1 { rank as f32 / (count - 1) as f32 } else { 0.0 };
+    Color::from_rgb(0.95, 0.25 + 0.6 * t, 0.2)
+}
+
+fn occupancy_pct(booked: usize, capacity: usize) -> f64 {
+    if capacity == 0 { 0.0 } else { booked as f64 * 100.0 / capacity as f64 }
+}
+
+// Plain-text confirmation email for a booking, shared by every place that shows or sends one
+fn build_email_body(booking: &Booking, show: &Show, price_format: &PriceFormat) -> String {
+    let mut body = format!("{} — Booking Confirmation\n\nDear {},\n\nThank you for your booking. Here are your details:\n\n", VENUE_NAME, booking.customer_name);
+    body.push_str(&format!("  Movie:       {}\n", show.name));
+    body.push_str(&format!("  Date:        {}\n", show.date));
+    body.push_str(&format!("  Time:        {}\n", show.time));
+    body.push_str(&format!("  Hall:        {}\n", show.hall));
+    body.push_str(&format!("  Seats:       {}\n", booking.seat_list()));
+    body.push_str(&format!("  Price:       {}\n", price_format.format(booking.price)));
+    if booking.service_charge > 0.0 {
+        body.push_str(&format!("  Service charge: {}\n", price_format.format(booking.service_charge)));
+        body.push_str(&format!("  Total:       {}\n", price_format.format(booking.grand_total)));
+    }
+    body.push_str(&format!("  Receipt No:  {}\n", booking.receipt_no));
+    body.push_str(&format!("  Booked at:   {}\n", booking.booking_time));
+    if let Some(group) = &booking.group_name {
+        body.push_str(&format!("  Account:     {}\n", group));
+    }
+    body.push_str(&format!("\nConfirmation code: {}\n\nPlease present this code at the entrance. Enjoy the show!\n— {}\n", booking.id, VENUE_NAME));
+    body
+}
+
+// Quotes a CSV field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Single-page printable ticket with inline styles so it renders the same in any browser
+fn build_ticket_html(booking: &Booking, show: &Show, price_format: &PriceFormat, qr_file: Option<&str>) -> String {
+    let rows = [
+        ("Date", format!("{} at {}", show.date, show.time)),
+        ("Hall", show.hall.clone()),
+        ("Seats", booking.seat_list()),
+        ("Price", price_format.format(booking.price)),
+        ("Customer", booking.customer_name.clone()),
+        ("Phone", booking.phone.clone().unwrap_or_else(|| "—".to_string())),
+        ("Receipt", booking.receipt_no.clone()),
+        ("Booked at", booking.booking_time.clone()),
+    ];
+    let mut payment = Vec::new();
+    if booking.service_charge > 0.0 {
+        payment.push(("Service charge", price_format.format(booking.service_charge)));
+        payment.push(("Total", price_format.format(booking.grand_total)));
+    }
+    if let (Some(tendered), Some(change)) = (booking.tendered, booking.change) {
+        payment.push(("Tendered", price_format.format(tendered)));
+        payment.push(("Change", price_format.format(change)));
+    }
+    let rows: String = rows.iter().chain(&payment)
+        .map(|(label, value)| format!("<tr><th style=\"text-align:left;padding:4px 12px 4px 0;color:#555\">{}</th><td style=\"padding:4px 0\">{}</td></tr>", label, html_escape(value)))
+        .collect();
+    let qr = qr_file.map_or(String::new(), |file| format!("<img src=\"{}\" alt=\"QR code\" style=\"display:block;margin:16px auto 0;width:160px;height:160px\">\n", html_escape(file)));
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Ticket {id}</title></head>\n\
+<body style=\"font-family:Arial,sans-serif;margin:0;padding:24px\">\n\
+<div style=\"max-width:480px;margin:auto;border:2px dashed #333;border-radius:12px;padding:24px;page-break-inside:avoid\">\n\
+<p style=\"margin:0;color:#888;letter-spacing:2px\">{venue}</p>\n\
+<h1 style=\"margin:8px 0 16px\">🎬 {movie}</h1>\n\
+<table style=\"border-collapse:collapse;width:100%\">{rows}</table>\n\
+{qr}<p style=\"margin:16px 0 0;font-family:monospace;font-size:12px\">Booking ID: {id}</p>\n\
+</div>\n</body></html>\n",
+        venue = VENUE_NAME,
+        movie = html_escape(&show.name),
+        rows = rows,
+        qr = qr,
+        id = html_escape(&booking.id),
+    )
+}
+
+// Renders the booking id as a QR code PNG for gate scanning
+fn save_qr_code(id: &str, path: &str) -> Result<(), String> {
+    let code = QrCode::new(id.as_bytes()).map_err(|e| e.to_string())?;
+    let image = code.render::<Luma<u8>>().min_dimensions(200, 200).build();
+    image.save(path).map_err(|e| e.to_string())
+}
+
+fn default_shows() -> Vec<Show> {
+    vec![
+        Show { id: 0, name: "Dune: Part Two".to_string(), date: "15-03-2024".to_string(), time: "18:00".to_string(), hall: "Hall 1".to_string(), price: 1500.0, available_seats: 0, overbook_pct: 0.0, transpose_grid: false, tags: vec!["IMAX".into(), "Subtitled".into()], aisle_after: default_aisles(), rows: 4, cols: 5, emoji: "🏜️".into(), price_multiplier: 1.0 },
+        Show { id: 1, name: "Oppenheimer".to_string(), date: "20-03-2024".to_string(), time: "20:30".to_string(), hall: "Hall 2".to_string(), price: 2250.0, available_seats: 0, overbook_pct: 0.0, transpose_grid: false, tags: vec!["IMAX".into()], aisle_after: default_aisles(), rows: 4, cols: 5, emoji: "💥".into(), price_multiplier: 1.0 },
+        Show { id: 2, name: "Barbie".to_string(), date: "22-03-2024".to_string(), time: "19:00".to_string(), hall: "Hall 3".to_string(), price: 2000.0, available_seats: 0, overbook_pct: 10.0, transpose_grid: false, tags: vec!["Family".into()], aisle_after: default_aisles(), rows: 4, cols: 5, emoji: "💖".into(), price_multiplier: 1.0 },
+        Show { id: 3, name: "Deadpool & Wolverine".to_string(), date: "25-03-2024".to_string(), time: "21:00".to_string(), hall: "Hall 4".to_string(), price: 1500.0, available_seats: 0, overbook_pct: 0.0, transpose_grid: false, tags: vec!["3D".into(), "18+".into()], aisle_after: default_aisles(), rows: 4, cols: 5, emoji: "⚔️".into(), price_multiplier: 1.0 },
+        Show { id: 4, name: "Inside Out 2".to_string(), date: "28-03-2024".to_string(), time: "17:30".to_string(), hall: "Hall 5".to_string(), price: 1500.0, available_seats: 0, overbook_pct: 0.0, transpose_grid: false, tags: vec!["3D".into(), "Family".into()], aisle_after: default_aisles(), rows: 4, cols: 5, emoji: "😊".into(), price_multiplier: 1.0 },
+        Show { id: 5, name: "Wicked".to_string(), date: "30-03-2024".to_string(), time: "19:30".to_string(), hall: "Grand Hall".to_string(), price: 2500.0, available_seats: 0, overbook_pct: 0.0, transpose_grid: false, tags: vec!["Family".into(), "Musical".into()], aisle_after: vec![2, 6], rows: 6, cols: 8, emoji: "🧙".into(), price_multiplier: 1.0 },
+    ]
+}
+
+// Lays out a hall of rows x cols seats with tiers assigned by row.
+// The two end seats of the back row are kept as accessible seats.
+fn new_seat_grid(rows: usize, cols: usize) -> Vec<Vec<Seat>> {
+    (0..rows).map(|row| {
+        (0..cols).map(|col| Seat {
+            row: char::from_u32('A' as u32 + row as u32).unwrap(),
+            col: col + 1,
+            is_booked: false,
+            booking_id: None,
+            tier: SeatTier::for_row(row),
+            is_reserved: row + 1 == rows && (col == 0 || col + 1 == cols),
+            booking_order: None,
+        }).collect()
+    }).collect()
+}
+
+// Seats that can be sold at all, i.e. the grid minus reserved seats
+fn sellable_seats(grid: &[Vec<Seat>]) -> usize {
+    grid.iter().flatten().filter(|s| !s.is_reserved).count()
+}
+
+// Takes its randomness from a v4 UUID so no extra RNG dependency is needed
+fn generate_booking_code() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    let code: String = bytes.iter().take(BOOKING_CODE_LEN).map(|b| BOOKING_CODE_ALPHABET[*b as usize % BOOKING_CODE_ALPHABET.len()] as char).collect();
+    format!("TH-{}", code)
+}
+
+// Cleans up a typed or pasted booking id, e.g. " id: th-7k9qx2 " from the success message
+fn normalize_booking_id(input: &str) -> String {
+    let id = input.trim();
+    let id = match id.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("id:") => id[3..].trim_start(),
+        _ => id,
+    };
+    id.trim().to_ascii_uppercase()
+}
+
+// Refund policy: full refund more than 24h before the show, half within 24h, nothing once it has started.
+// A show whose start time can't be parsed is refunded in full.
+fn refund_amount(price: f64, starts_at: Option<NaiveDateTime>, now: NaiveDateTime) -> f64 {
+    match starts_at {
+        Some(starts) if now >= starts => 0.0,
+        Some(starts) if starts - now <= Duration::hours(24) => price * 0.5,
+        _ => price,
+    }
+}
+
+fn group_discount_rate(seat_count: usize) -> f64 {
+    GROUP_DISCOUNTS.iter().find(|&&(min_seats, _)| seat_count >= min_seats).map_or(0.0, |&(_, rate)| rate)
+}
+
+// Blank means a non-cash sale. Otherwise returns (tendered, change); compared in whole cents so
+// handing over exactly the displayed total is never rejected by floating-point noise. The amount is
+// read with the same separators prices are shown with, e.g. "1.500,50" under a European format.
+fn cash_change(total: f64, tendered: &str, price_format: &PriceFormat) -> Result<Option<(f64, f64)>, String> {
+    let tendered = tendered.trim();
+    if tendered.is_empty() {
+        return Ok(None);
+    }
+    let normalized: String = tendered.chars()
+        .filter(|&c| c != price_format.thousands_separator)
+        .map(|c| if c == price_format.decimal_separator { '.' } else { c })
+        .collect();
+    let Some(amount) = normalized.parse::<f64>().ok().filter(|a| a.is_finite() && *a >= 0.0) else {
+        return Err("Amount tendered must be a number".to_string());
+    };
+    let (amount_cents, total_cents) = ((amount * 100.0).round(), (total * 100.0).round());
+    if amount_cents < total_cents {
+        return Err(format!("Amount tendered is {} short of the total", price_format.format((total_cents - amount_cents) / 100.0)));
+    }
+    Ok(Some((amount, (amount_cents - total_cents) / 100.0)))
+}
+
+// Optional contact number: blank means none, otherwise 10-15 digits once spaces and a leading '+' are removed
+fn validate_phone(phone: &str) -> Result<Option<String>, String> {
+    let phone = phone.trim();
+    if phone.is_empty() {
+        return Ok(None);
+    }
+    let digits: String = phone.strip_prefix('+').unwrap_or(phone).chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        Err("Phone number may only contain digits, spaces and a leading +".to_string())
+    } else if !(10..=15).contains(&digits.len()) {
+        Err("Phone number must have 10 to 15 digits".to_string())
+    } else {
+        Ok(Some(phone.to_string()))
+    }
+}
+
+fn validate_customer_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    let len = name.chars().count();
+    if len == 0 {
+        Err("Please enter customer name".to_string())
+    } else if len < 2 {
+        Err("Customer name must be at least 2 characters".to_string())
+    } else if len > 50 {
+        Err("Customer name must be at most 50 characters".to_string())
+    } else if name.chars().any(|c| c.is_ascii_digit()) {
+        Err("Customer name cannot contain digits".to_string())
+    } else if name.chars().any(char::is_control) {
+        Err("Customer name contains invalid characters".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Appends a JSON line to audit.log. Auditing is best-effort: an I/O failure must never undo or block a booking.
+fn append_audit(action: &str, booking: &Booking, refund: Option<f64>) {
+    append_audit_entry(&AuditEntry {
+        action: action.to_string(),
+        booking_id: booking.id.clone(),
+        seats: booking.seats.clone(),
+        timestamp: Local::now().to_rfc3339(),
+        refund,
+    });
+}
+
+fn append_audit_entry(entry: &AuditEntry) {
+    let Ok(line) = serde_json::to_string(&entry) else { return };
+    if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open("audit.log") {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+// Audit entries oldest first; unreadable lines are skipped
+fn read_audit() -> Vec<AuditEntry> {
+    fs::read_to_string("audit.log")
+        .map(|log| log.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+// Reads the show lineup, or Ok(None) if there is no file. Bookings refer to shows by id, so the file is
+// all-or-nothing: dropping or renumbering one entry would move every later show's bookings onto another
+// show. Every problem found is returned so they can all be fixed in one go.
+fn load_shows(path: &str) -> Result<Option<Vec<Show>>, Vec<String>> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(vec![format!("{} could not be read: {}", path, e)]),
+    };
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&json).map_err(|e| vec![format!("{} is not a JSON list of shows: {}", path, e)])?;
+    let mut shows: Vec<Show> = Vec::new();
+    let mut problems = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let problem = match serde_json::from_value::<Show>(entry) {
+            Err(e) => Some(e.to_string()),
+            Ok(show) if show.id != i => Some(format!("id {} should be {}; ids must run 0, 1, 2... in file order", show.id, i)),
+            Ok(show) if show.price < 0.0 => Some("negative price".to_string()),
+            Ok(show) if !(show.price_multiplier.is_finite() && show.price_multiplier > 0.0) => Some("price multiplier must be positive".to_string()),
+            Ok(show) if !(1..=MAX_HALL_ROWS).contains(&show.rows) => Some(format!("rows must be 1-{}", MAX_HALL_ROWS)),
+            Ok(show) if !(1..=MAX_HALL_COLS).contains(&show.cols) => Some(format!("cols must be 1-{}", MAX_HALL_COLS)),
+            Ok(show) => {
+                shows.push(show);
+                None
+            }
+        };
+        if let Some(problem) = problem {
+            problems.push(format!("show #{} in {}: {}", i + 1, path, problem));
+        }
+    }
+    if problems.is_empty() { Ok(Some(shows)) } else { Err(problems) }
+}
+
+// Parses a seat code such as "B3" into zero-based (row, col) grid indices
+fn parse_seat_code(code: &str) -> Option<(usize, usize)> {
+    let code = code.trim();
+    let row = code.chars().next()?.to_ascii_uppercase();
+    let col: usize = code[row.len_utf8()..].trim().parse().ok()?;
+    if !row.is_ascii_uppercase() || col == 0 {
+        return None;
+    }
+    Some((row as usize - 'A' as usize, col - 1))
+}
+
+// Writes to a sibling temp file and renames it into place, so a crash mid-write never leaves a truncated file behind
+fn write_file_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents.as_ref())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+// Frees every seat held by the booking and returns their (row, col) positions with the sale order
+// each one had, so an undo can put the popularity heat map back as it was
+fn free_booking_seats(seats: &mut [Vec<Seat>], booking_id: &str) -> Vec<FreedSeat> {
+    let mut freed = Vec::new();
+    for (r, row) in seats.iter_mut().enumerate() {
+        for (c, seat) in row.iter_mut().enumerate() {
+            if seat.booking_id.as_deref() == Some(booking_id) {
+                seat.is_booked = false;
+                seat.booking_id = None;
+                freed.push(((r, c), seat.booking_order.take()));
+            }
+        }
+    }
+    freed
+}
+
+fn container_dark_style(theme: &Theme) -> container::Appearance {
+    let background = if *theme == Theme::Light { Color::from_rgb(0.95, 0.95, 0.97) } else { Color::from_rgb(0.05, 0.05, 0.1) };
+    container::Appearance { background: Some(background.into()), ..Default::default() }
+}
+
+fn container_card_style(theme: &Theme) -> container::Appearance {
+    let (background, border) = if *theme == Theme::Light {
+        (Color::WHITE, Color::from_rgb(0.75, 0.75, 0.8))
+    } else {
+        (Color::from_rgb(0.1, 0.1, 0.15), Color::from_rgb(0.3, 0.3, 0.4))
+    };
     container::Appearance {
-        background: Some(Color::from_rgb(0.1, 0.1, 0.15).into()),
-        border: iced::Border { color: Color::from_rgb(0.3, 0.3, 0.4), width: 1.0, radius: 8.0.into() },
+        background: Some(background.into()),
+        border: iced::Border { color: border, width: 1.0, radius: 8.0.into() },
         ..Default::default()
     }
 }
 
+// Bar chart of revenue per show, scaled to the best-selling show, with names under the bars
+struct RevenueChart {
+    bars: Vec<(String, f64)>,
+}
+
+impl RevenueChart {
+    const SLOT_WIDTH: f32 = 110.0;
+    const LABEL_HEIGHT: f32 = 40.0;
+}
+
+impl canvas::Program<Message> for RevenueChart {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let max = self.bars.iter().map(|(_, revenue)| *revenue).fold(0.0, f64::max);
+        let slot = bounds.width / self.bars.len().max(1) as f32;
+        let chart_height = bounds.height - Self::LABEL_HEIGHT;
+        let label_color = theme.palette().text;
+        for (i, (name, revenue)) in self.bars.iter().enumerate() {
+            let height = if max > 0.0 { (revenue / max) as f32 * (chart_height - 20.0) } else { 0.0 };
+            let x = i as f32 * slot + slot * 0.2;
+            frame.fill_rectangle(Point::new(x, chart_height - height), Size::new(slot * 0.6, height), Color::from_rgb(0.3, 0.6, 0.9));
+            let label = if name.chars().count() > 12 { format!("{}…", name.chars().take(11).collect::<String>()) } else { name.clone() };
+            frame.fill_text(canvas::Text {
+                content: label,
+                position: Point::new(i as f32 * slot + slot / 2.0, chart_height + 8.0),
+                color: label_color,
+                size: 13.0.into(),
+                horizontal_alignment: iced::alignment::Horizontal::Center,
+                ..canvas::Text::default()
+            });
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+struct SeatButtonStyle {
+    background: Color,
+    tier: SeatTier,
+}
+
+impl button::StyleSheet for SeatButtonStyle {
+    type Style = Theme;
+
+    fn active(&self, _style: &Theme) -> button::Appearance {
+        let width = if self.tier == SeatTier::Standard { 1.0 } else { 3.0 };
+        button::Appearance {
+            background: Some(self.background.into()),
+            border: iced::Border { color: self.tier.border_color(), width, radius: 4.0.into() },
+            ..Default::default()
+        }
+    }
+}
+
 fn menu_button<'a>(label: &str, message: Message) -> Button<'a, Message> {
     button(text(label).size(20)).on_press(message).padding(20).width(Length::Fixed(400.0))
 }
 
 // FIXED: Added '_ to return type
-fn show_card(show: &Show) -> Element<'_, Message> {
+fn show_card<'a>(show: &'a Show, price_format: &PriceFormat, sold_out: bool) -> Element<'a, Message> {
+    let chips = show.tags.iter().fold(row![].spacing(6), |chips, tag| {
+        chips.push(container(text(tag).size(12)).padding([2, 8]).style(container_card_style))
+    });
     container(column![
-        text(&show.name).size(24),
-        text(format!("💺 {} seats available", show.available_seats)).size(14),
-        button("Book Now →").on_press(Message::SelectShow(show.id)).padding(10),
+        text(format!("{} {}", show.emoji(), show.name)).size(24),
+        chips,
+        text(format!("💰 {} | 💺 {} seats available", show.price_label(price_format), show.available_seats)).size(14),
+        if sold_out {
+            Element::from(text("SOLD OUT").size(18).style(Color::from_rgb(0.5, 0.5, 0.5)))
+        } else {
+            button("Book Now →").on_press(Message::SelectShow(show.id)).padding(10).into()
+        },
     ].spacing(10).padding(20)).style(container_card_style).width(Length::Fill).into()
 }
 
+// Wraps a seat layout in a scroll area capped at SEAT_GRID_MAX_HEIGHT; the screen label stays pinned above it
+fn screen_and_seat_grid(seat_grid: iced::widget::Column<'_, Message>) -> Element<'_, Message> {
+    let seat_grid = scrollable(seat_grid).direction(scrollable::Direction::Both {
+        vertical: scrollable::Properties::default(),
+        horizontal: scrollable::Properties::default(),
+    });
+    column![text("🎬 SCREEN").size(20), container(seat_grid).max_height(SEAT_GRID_MAX_HEIGHT)].spacing(10).align_items(Alignment::Center).into()
+}
+
 // FIXED: Added '_ to return type
-fn create_seat_button(seat: &Seat, is_selected: bool, row: usize, col: usize) -> Element<'_, Message> {
-    let emoji = if seat.is_booked { "🔴" } else if is_selected { "🟡" } else { "🟢" };
-    let btn = button(text(emoji).size(24)).padding(8);
-    if !seat.is_booked { btn.on_press(Message::SelectSeat(row, col)).into() } else { btn.into() }
+fn create_seat_button<'a>(seat: &Seat, highlight: SeatHighlight, row: usize, col: usize, palette: &SeatPalette, mode: SeatRenderMode, on_select: fn(usize, usize) -> Message) -> Element<'a, Message> {
+    let style = iced::theme::Button::custom(SeatButtonStyle { background: palette.color_for(seat, highlight), tier: seat.tier });
+    let btn = if mode == SeatRenderMode::Numeric {
+        let label = text(seat.col).size(16).width(24).horizontal_alignment(iced::alignment::Horizontal::Center);
+        button(label).padding(8).style(style)
+    } else if palette.colored_seats {
+        button(Space::new(24, 24)).padding(8).style(style)
+    } else {
+        let emoji = match (seat.is_reserved, seat.is_booked, highlight) {
+            (true, _, _) => "♿",
+            (_, true, SeatHighlight::JustBooked) => "🟣",
+            (_, true, _) => "🔴",
+            (_, false, SeatHighlight::Selected) => "🟡",
+            _ => "🟢",
+        };
+        let btn = button(text(emoji).size(24)).padding(8);
+        if seat.tier == SeatTier::Standard {
+            btn
+        } else {
+            btn.style(iced::theme::Button::custom(SeatButtonStyle { background: Color::from_rgb(0.1, 0.1, 0.15), tier: seat.tier }))
+        }
+    };
+    // Unavailable seats stay clickable so the handler can explain why they can't be chosen
+    btn.on_press(on_select(row, col)).into()
 }
 
 fn stat_card<'a>(label: impl Into<String>, value: impl Into<String>) -> Element<'a, Message> {
@@ -410,8 +2975,683 @@ fn stat_card<'a>(label: impl Into<String>, value: impl Into<String>) -> Element<
 }
 
 fn main() -> iced::Result {
+    // The lineup in shows.json replaces the built-in shows
+    let shows = match load_shows("shows.json") {
+        Ok(shows) => shows.unwrap_or_else(default_shows),
+        Err(problems) => {
+            for problem in problems {
+                eprintln!("error: {}", problem);
+            }
+            eprintln!("error: shows.json was not loaded; fix it (or remove it to use the built-in shows) and restart");
+            std::process::exit(1);
+        }
+    };
     TheatreApp::run(Settings {
+        flags: shows,
         window: iced::window::Settings { size: iced::Size::new(900.0, 700.0), ..Default::default() },
         ..Default::default()
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Handlers write tickets, audit.log and exports to the working directory, so tests run from a
+    // scratch directory. Shows are moved into the future so no-show release never kicks in.
+    fn test_app() -> TheatreApp {
+        static SCRATCH_DIR: std::sync::Once = std::sync::Once::new();
+        SCRATCH_DIR.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("theatre_app_tests_{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            std::env::set_current_dir(&dir).unwrap();
+        });
+        let shows = default_shows().into_iter().map(|show| Show { date: "15-03-2099".to_string(), ..show }).collect();
+        TheatreApp::with_shows(shows, Preferences::default(), SeatPalette::default())
+    }
+
+    // Held by tests that write or read files with fixed names such as bookings_export.json
+    static FIXED_FILES: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    // Books the given seats through the UI messages and returns the new booking's id
+    fn book(app: &mut TheatreApp, show_id: usize, seats: &[(usize, usize)], name: &str) -> String {
+        app.handle_message(Message::SelectShow(show_id));
+        for &(row, col) in seats {
+            app.handle_message(Message::SelectSeat(row, col));
+        }
+        app.handle_message(Message::CustomerNameChanged(name.to_string()));
+        app.last_confirm_instant = None;
+        app.handle_message(Message::ConfirmBooking);
+        assert_eq!(app.error_message, None);
+        app.bookings.last().unwrap().id.clone()
+    }
+
+    fn sample_show(id: usize, name: &str) -> Show {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "name": name, "date": "15-03-2099", "time": "18:00", "hall": format!("Hall {}", id + 1), "price": 1500.0,
+        })).unwrap()
+    }
+
+    fn sample_booking(id: &str, show_id: usize, name: &str, seats: &[&str], price: f64) -> Booking {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "show_id": show_id, "customer_name": name, "seats": seats,
+            "booking_time": "01-03-2099 10:00:00", "price": price, "receipt_no": "R-000042",
+        })).unwrap()
+    }
+
+    #[test]
+    fn email_body_lists_key_booking_fields() {
+        let show = sample_show(0, "Dune: Part Two");
+        let mut booking = sample_booking("TH-7K9QX2", 0, "Ada Lovelace", &["A1", "A2"], 3000.0);
+        booking.group_name = Some("Acme Corp".to_string());
+        let body = build_email_body(&booking, &show, &PriceFormat::default());
+        for expected in ["Ada Lovelace", "Dune: Part Two", "15-03-2099", "18:00", "Hall 1", "A1, A2", "LKR 3,000.00", "R-000042", "Acme Corp", "TH-7K9QX2"] {
+            assert!(body.contains(expected), "missing {:?} in:\n{}", expected, body);
+        }
+        assert!(!body.contains("Service charge"));
+    }
+
+    #[test]
+    fn email_body_includes_service_charge_and_total() {
+        let show = sample_show(0, "Dune: Part Two");
+        let mut booking = sample_booking("TH-7K9QX2", 0, "Ada Lovelace", &["A1", "A2"], 3000.0);
+        booking.service_charge = 150.0;
+        booking.grand_total = 3150.0;
+        let body = build_email_body(&booking, &show, &PriceFormat::default());
+        assert!(body.contains("Service charge: LKR 150.00"), "{}", body);
+        assert!(body.contains("Total:       LKR 3,150.00"), "{}", body);
+    }
+
+    #[test]
+    fn ticket_html_contains_seats_and_booking_id() {
+        let show = sample_show(0, "Dune: Part Two");
+        let booking = sample_booking("TH-7K9QX2", 0, "Ada Lovelace", &["A1", "A2"], 3000.0);
+        let html = build_ticket_html(&booking, &show, &PriceFormat::default(), Some("ticket_TH-7K9QX2_qr.png"));
+        assert!(html.contains("A1, A2"));
+        assert!(html.contains("TH-7K9QX2"));
+        assert!(html.contains("ticket_TH-7K9QX2_qr.png"));
+    }
+
+    // Minimal RFC 4180 reader for one record, enough to check csv_field's quoting
+    fn parse_csv_line(line: &str) -> Vec<String> {
+        let (mut fields, mut field, mut quoted, mut chars) = (Vec::new(), String::new(), false, line.chars().peekable());
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => { field.push('"'); chars.next(); }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                c => field.push(c),
+            }
+        }
+        fields.push(field);
+        fields
+    }
+
+    #[test]
+    fn csv_fields_with_commas_and_quotes_round_trip() {
+        let values = ["Smith, John", "Said \"hi\"", "plain"];
+        let line = values.iter().map(|v| csv_field(v)).collect::<Vec<_>>().join(",");
+        assert_eq!(line, "\"Smith, John\",\"Said \"\"hi\"\"\",plain");
+        assert_eq!(parse_csv_line(&line), values);
+    }
+
+    #[test]
+    fn customer_name_validation_reports_each_rejection() {
+        assert_eq!(validate_customer_name("   "), Err("Please enter customer name".to_string()));
+        assert_eq!(validate_customer_name("A"), Err("Customer name must be at least 2 characters".to_string()));
+        assert_eq!(validate_customer_name(&"a".repeat(51)), Err("Customer name must be at most 50 characters".to_string()));
+        assert_eq!(validate_customer_name("Agent 007"), Err("Customer name cannot contain digits".to_string()));
+        assert_eq!(validate_customer_name("Ada\u{7}Lovelace"), Err("Customer name contains invalid characters".to_string()));
+    }
+
+    #[test]
+    fn customer_name_with_spaces_and_apostrophes_is_accepted() {
+        assert_eq!(validate_customer_name("  Mary-Kate O'Brien  "), Ok(()));
+        assert_eq!(validate_customer_name(&"a".repeat(50)), Ok(()));
+    }
+
+    #[test]
+    fn group_discount_starts_at_four_and_six_seats() {
+        assert_eq!(group_discount_rate(3), 0.0);
+        assert_eq!(group_discount_rate(4), 0.10);
+        assert_eq!(group_discount_rate(5), 0.10);
+        assert_eq!(group_discount_rate(6), 0.15);
+    }
+
+    #[test]
+    fn compute_total_applies_group_discount_to_tiered_prices() {
+        let app = test_app();
+        // Row C is Standard at 1500 per seat
+        let seats = |n: usize| (0..n).map(|c| (2, c)).collect::<Vec<_>>();
+        let (gross, net) = app.compute_total(0, &seats(3));
+        assert_eq!((gross, net), (4500.0, 4500.0));
+        let (gross, net) = app.compute_total(0, &seats(4));
+        assert_eq!(gross, 6000.0);
+        assert!((net - 5400.0).abs() < 1e-9);
+        let (gross, net) = app.compute_total(0, &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (2, 3)]);
+        assert_eq!(gross, 2250.0 + 1875.0 + 4.0 * 1500.0);
+        assert!((net - gross * 0.85).abs() < 1e-9);
+    }
+
+    #[test]
+    fn booking_then_cancel_logs_two_ordered_audit_entries() {
+        let mut app = test_app();
+        let id = book(&mut app, 0, &[(2, 0), (2, 1)], "Ada Lovelace");
+        app.handle_message(Message::BookingIdChanged(id.clone()));
+        app.handle_message(Message::RequestCancel);
+        app.handle_message(Message::CancelBookingConfirm);
+        let entries: Vec<AuditEntry> = read_audit().into_iter().filter(|e| e.booking_id == id).collect();
+        let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["book", "cancel"]);
+        assert_eq!(entries[0].seats, ["C1", "C2"]);
+        assert!(entries[0].timestamp <= entries[1].timestamp);
+        assert!(entries[1].refund.is_some());
+    }
+
+    #[test]
+    fn refund_depends_on_time_to_showtime() {
+        let starts = NaiveDateTime::parse_from_str("15-03-2099 18:00", "%d-%m-%Y %H:%M").unwrap();
+        assert_eq!(refund_amount(1000.0, Some(starts), starts - Duration::hours(48)), 1000.0);
+        assert_eq!(refund_amount(1000.0, Some(starts), starts - Duration::hours(24) - Duration::minutes(1)), 1000.0);
+        assert_eq!(refund_amount(1000.0, Some(starts), starts - Duration::hours(3)), 500.0);
+        assert_eq!(refund_amount(1000.0, Some(starts), starts), 0.0);
+        assert_eq!(refund_amount(1000.0, Some(starts), starts + Duration::hours(1)), 0.0);
+        assert_eq!(refund_amount(1000.0, None, starts), 1000.0);
+    }
+
+    #[test]
+    fn unpaid_reservation_is_not_refunded() {
+        let mut app = test_app();
+        app.prefs.kiosk_mode = true;
+        let id = book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        let booking = app.bookings.iter().find(|b| b.id == id).unwrap();
+        assert!(booking.expires_at.is_some() && booking.grand_total > 0.0);
+        assert_eq!(app.refund_for(booking), 0.0);
+        app.handle_message(Message::BookingIdChanged(id.clone()));
+        app.handle_message(Message::RequestCancel);
+        app.handle_message(Message::CancelBookingConfirm);
+        let cancel = read_audit().into_iter().find(|e| e.booking_id == id && e.action == "cancel").unwrap();
+        assert_eq!(cancel.refund, Some(0.0));
+    }
+
+    #[test]
+    fn export_then_import_reproduces_the_seat_map() {
+        let mut app = test_app();
+        book(&mut app, 0, &[(0, 1), (0, 2)], "Ada Lovelace");
+        book(&mut app, 5, &[(3, 4)], "Grace Hopper");
+        let exported = {
+            let _lock = FIXED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+            app.export_records().unwrap();
+            fs::read_to_string("bookings_export.json").unwrap()
+        };
+
+        // Older exports stored a single "seat" string instead of a list
+        let mut records: Vec<serde_json::Value> = serde_json::from_str(&exported).unwrap();
+        records.push(serde_json::json!({
+            "id": "legacy-1", "show_id": 1, "customer_name": "Alan Turing", "seat": "B3",
+            "booking_time": "01-03-2099 09:00:00", "price": 2812.5,
+        }));
+        let path = format!("import_{}.json", Uuid::new_v4());
+        fs::write(&path, serde_json::to_string(&records).unwrap()).unwrap();
+
+        let mut fresh = test_app();
+        assert_eq!(fresh.import_records(&path).unwrap(), (3, 0));
+        let booked = |app: &TheatreApp, show: usize| -> Vec<(char, usize, Option<String>)> {
+            app.seats[show].iter().flatten().filter(|s| s.is_booked).map(|s| (s.row, s.col, s.booking_id.clone())).collect()
+        };
+        assert_eq!(booked(&fresh, 0), booked(&app, 0));
+        assert_eq!(booked(&fresh, 5), booked(&app, 5));
+        assert_eq!(booked(&fresh, 1), [('B', 3, Some("legacy-1".to_string()))]);
+        assert_eq!(fresh.shows[0].available_seats, app.shows[0].available_seats);
+    }
+
+    fn write_shows_file(shows: serde_json::Value) -> String {
+        let dir = std::env::temp_dir().join(format!("theatre_app_shows_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shows.json").to_string_lossy().into_owned();
+        fs::write(&path, shows.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn sample_shows_file_loads_with_defaults() {
+        let path = write_shows_file(serde_json::json!([
+            { "id": 0, "name": "Dune: Part Two", "date": "15-03-2099", "time": "18:00", "hall": "Hall 1", "price": 1500.0 },
+            { "id": 1, "name": "Wicked", "date": "30-03-2099", "time": "19:30", "hall": "Grand Hall", "price": 2500.0, "rows": 6, "cols": 8, "aisle_after": [2, 6] },
+        ]));
+        let shows = load_shows(&path).unwrap().unwrap();
+        assert_eq!(shows.len(), 2);
+        assert_eq!((shows[0].rows, shows[0].cols, shows[0].price_multiplier), (4, 5, 1.0));
+        assert_eq!((shows[1].id, shows[1].rows, shows[1].cols), (1, 6, 8));
+        assert!(matches!(load_shows("no_such_dir/shows.json"), Ok(None)));
+    }
+
+    #[test]
+    fn shows_file_with_a_bad_entry_is_rejected_whole() {
+        let path = write_shows_file(serde_json::json!([
+            { "id": 0, "name": "Dune: Part Two", "date": "15-03-2099", "time": "18:00", "hall": "Hall 1", "price": -5.0 },
+            { "id": 1, "name": "Oppenheimer", "date": "20-03-2099", "time": "20:30", "hall": "Hall 2", "price": 2250.0, "rows": 30 },
+            { "id": 3, "name": "Barbie", "date": "22-03-2099", "time": "19:00", "hall": "Hall 3", "price": 2000.0 },
+            { "id": 3, "name": "Deadpool & Wolverine", "date": "25-03-2099", "time": "21:00", "hall": "Hall 4", "price": 1500.0, "cols": 0 },
+        ]));
+        let problems = load_shows(&path).unwrap_err();
+        assert_eq!(problems.len(), 4);
+        assert!(problems[0].contains("negative price"));
+        assert!(problems[1].contains("rows must be 1-26"));
+        assert!(problems[2].contains("id 3 should be 2"));
+        assert!(problems[3].contains("cols must be 1-50"));
+    }
+
+    #[test]
+    fn clear_all_bookings_frees_every_seat_and_keeps_a_backup() {
+        let mut app = test_app();
+        let id = book(&mut app, 0, &[(2, 0), (2, 1)], "Ada Lovelace");
+        book(&mut app, 5, &[(0, 0)], "Grace Hopper");
+        let backup = {
+            let _lock = FIXED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+            app.clear_all_bookings().unwrap()
+        };
+
+        assert!(app.bookings.is_empty());
+        assert!(app.seats.iter().flatten().flatten().all(|s| !s.is_booked && s.booking_id.is_none()));
+        for (show, grid) in app.shows.iter().zip(&app.seats) {
+            assert_eq!(show.available_seats, sellable_seats(grid));
+        }
+        assert_eq!(app.stats_cache.total_bookings, 0);
+        assert!(fs::read_to_string(backup).unwrap().contains(&id));
+    }
+
+    #[test]
+    fn booking_codes_are_short_and_skip_ambiguous_characters() {
+        let code = generate_booking_code();
+        let body = code.strip_prefix("TH-").unwrap();
+        assert_eq!(body.len(), BOOKING_CODE_LEN);
+        assert!(body.bytes().all(|b| BOOKING_CODE_ALPHABET.contains(&b)));
+    }
+
+    #[test]
+    fn booking_code_collisions_are_retried() {
+        let mut app = test_app();
+        let taken = book(&mut app, 0, &[(2, 0)], "Ada Lovelace");
+        // An existing code is skipped even when typed in a different case
+        let candidates = [taken.to_lowercase(), taken.clone(), "TH-NEW234".to_string()];
+        assert_eq!(app.first_unused_code(candidates), "TH-NEW234");
+        assert_ne!(app.new_booking_code(), taken);
+    }
+
+
+    #[test]
+    fn combined_ticket_file_holds_every_paid_booking() {
+        let _lock = FIXED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        let mut app = test_app();
+        let first = book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        let second = book(&mut app, 1, &[(1, 1), (1, 2)], "Alan Turing");
+        let mut reserved = sample_booking("TH-UNPAID", 0, "Grace Hopper", &["B1"], 1500.0);
+        reserved.expires_at = Some("15-03-2099 10:15:00".to_string());
+        app.bookings.push(reserved);
+        assert_eq!(app.export_all_tickets().unwrap(), (2, 0));
+        let combined = fs::read_to_string("all_tickets.txt").unwrap();
+        assert!(combined.contains(&first) && combined.contains(&second));
+        assert!(!combined.contains("TH-UNPAID"));
+        assert_eq!(combined.matches('\x0c').count(), 1);
+    }
+
+
+    #[test]
+    fn normalize_booking_id_cleans_typed_and_pasted_ids() {
+        assert_eq!(normalize_booking_id("th-7k9qx2"), "TH-7K9QX2");
+        assert_eq!(normalize_booking_id("  TH-7K9QX2 \n"), "TH-7K9QX2");
+        assert_eq!(normalize_booking_id("ID: TH-7K9QX2"), "TH-7K9QX2");
+        assert_eq!(normalize_booking_id(" id:th-7k9qx2 "), "TH-7K9QX2");
+    }
+
+
+    #[test]
+    fn capacity_alert_fires_once_when_crossing_threshold() {
+        let mut app = test_app();
+        let open: Vec<(usize, usize)> = app.seats[0].iter().enumerate()
+            .flat_map(|(r, row)| row.iter().enumerate().filter(|(_, s)| !s.is_reserved).map(move |(c, _)| (r, c)))
+            .collect();
+        let mut alerts = 0;
+        for (i, &seat) in open.iter().enumerate() {
+            book(&mut app, 0, &[seat], &format!("Customer {}", "ABCDEFGHIJKLMNOPQRSTUVWXYZ".as_bytes()[i] as char));
+            if app.success_message.as_deref().is_some_and(|m| m.contains("% full")) {
+                alerts += 1;
+                let sold = i + 1;
+                assert!(occupancy_pct(sold, open.len()) >= CAPACITY_ALERT_PCT && occupancy_pct(sold - 1, open.len()) < CAPACITY_ALERT_PCT);
+            }
+        }
+        assert_eq!(alerts, 1);
+        let logged = read_audit().into_iter().filter(|e| e.action == "capacity_alert" && app.bookings.iter().any(|b| b.id == e.booking_id)).count();
+        assert_eq!(logged, 1);
+    }
+
+
+    #[test]
+    fn seat_taken_after_selection_blocks_confirmation() {
+        let mut app = test_app();
+        app.handle_message(Message::SelectShow(2));
+        app.handle_message(Message::SelectSeat(0, 0));
+        app.handle_message(Message::SelectSeat(0, 1));
+        app.handle_message(Message::CustomerNameChanged("Ada Lovelace".to_string()));
+        // Someone else gets A2 in the meantime
+        let seat = &mut app.seats[2][0][1];
+        seat.is_booked = true;
+        seat.booking_id = Some("TH-OTHER1".to_string());
+        app.last_confirm_instant = None;
+        app.handle_message(Message::ConfirmBooking);
+        assert!(app.error_message.as_deref().is_some_and(|e| e.contains("A2") && e.contains("no longer available")));
+        assert!(app.bookings.is_empty());
+        assert_eq!(app.selection(2), &[(0, 0)]);
+        assert!(!app.seats[2][0][0].is_booked);
+        assert_eq!(app.seats[2][0][1].booking_id.as_deref(), Some("TH-OTHER1"));
+    }
+
+
+    #[test]
+    fn stats_report_lists_each_show_with_its_revenue() {
+        let _lock = FIXED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        let mut app = test_app();
+        book(&mut app, 0, &[(0, 0), (0, 1)], "Ada Lovelace");
+        book(&mut app, 3, &[(2, 2)], "Alan Turing");
+        app.export_stats().unwrap();
+        let report = fs::read_to_string("statistics_report.txt").unwrap();
+        for show in &app.shows {
+            let revenue: f64 = app.bookings.iter().filter(|b| b.show_id == show.id).map(|b| b.price).sum();
+            let line = report.lines().find(|l| l.trim_start().starts_with(&format!("{}:", show.name))).unwrap();
+            assert!(line.ends_with(&format!("revenue {}", app.format_price(revenue))), "{}", line);
+        }
+        let total: f64 = app.bookings.iter().map(|b| b.price).sum();
+        assert!(total > 0.0 && report.contains(&format!("Total revenue: {}", app.format_price(total))));
+    }
+
+
+    #[test]
+    fn undoing_a_cancellation_restores_sale_order() {
+        let mut app = test_app();
+        book(&mut app, 1, &[(0, 0)], "Ada Lovelace");
+        let id = book(&mut app, 1, &[(1, 0), (1, 1)], "Alan Turing");
+        book(&mut app, 1, &[(2, 0)], "Grace Hopper");
+        let orders = |app: &TheatreApp| app.seats[1].iter().flatten().map(|s| s.booking_order).collect::<Vec<_>>();
+        let before = orders(&app);
+        app.handle_message(Message::BookingIdChanged(id.clone()));
+        app.handle_message(Message::RequestCancel);
+        app.handle_message(Message::CancelBookingConfirm);
+        assert_eq!(app.seats[1][1][0].booking_order, None);
+        app.handle_message(Message::Undo);
+        assert_eq!(app.error_message, None);
+        assert_eq!(orders(&app), before);
+        assert_eq!(app.seats[1][1][1].booking_id.as_deref(), Some(id.as_str()));
+    }
+
+
+    #[test]
+    fn cash_change_handles_exact_over_and_under_payment() {
+        let format = PriceFormat::default();
+        assert_eq!(cash_change(1500.0, "", &format), Ok(None));
+        assert_eq!(cash_change(1500.0, "1,500", &format), Ok(Some((1500.0, 0.0))));
+        assert_eq!(cash_change(0.1 + 0.2, "0.30", &format), Ok(Some((0.3, 0.0))));
+        assert_eq!(cash_change(1500.0, "2,000.50", &format), Ok(Some((2000.5, 500.5))));
+        let short = cash_change(1500.0, "1000", &format).unwrap_err();
+        assert!(short.contains(&format.format(500.0)), "{}", short);
+        assert!(cash_change(1500.0, "abc", &format).is_err());
+    }
+
+    #[test]
+    fn cash_change_reads_amounts_in_the_configured_format() {
+        let format = PriceFormat { currency: "€".to_string(), thousands_separator: '.', decimal_separator: ',' };
+        assert_eq!(cash_change(1500.25, "1.500,25", &format), Ok(Some((1500.25, 0.0))));
+        assert_eq!(cash_change(1500.0, "2.000,50", &format), Ok(Some((2000.5, 500.5))));
+        assert!(cash_change(1500.0, "1.000", &format).is_err());
+    }
+
+
+    #[test]
+    fn leaving_with_typed_details_asks_first() {
+        let mut app = test_app();
+        app.handle_message(Message::SelectShow(0));
+        app.handle_message(Message::SelectSeat(0, 0));
+        app.handle_message(Message::CustomerNameChanged("Ada Lovelace".to_string()));
+        app.handle_message(Message::ChangeView(View::ShowSelection));
+        assert_eq!(app.pending_view_change, Some(View::ShowSelection));
+        assert_eq!(app.current_view, View::Booking);
+        app.handle_message(Message::CancelLeave);
+        assert_eq!(app.pending_view_change, None);
+        assert_eq!(app.customer_name, "Ada Lovelace");
+        app.handle_message(Message::ChangeView(View::ShowSelection));
+        app.handle_message(Message::ConfirmLeave);
+        assert_eq!(app.current_view, View::ShowSelection);
+        assert!(app.customer_name.is_empty());
+        assert_eq!(app.selection(0), &[(0, 0)]);
+    }
+
+    #[test]
+    fn leaving_with_only_a_selection_keeps_it_without_asking() {
+        let mut app = test_app();
+        app.handle_message(Message::SelectShow(0));
+        app.handle_message(Message::SelectSeat(0, 0));
+        app.handle_message(Message::ChangeView(View::ShowSelection));
+        assert_eq!(app.pending_view_change, None);
+        assert_eq!(app.current_view, View::ShowSelection);
+        app.handle_message(Message::SelectShow(0));
+        assert_eq!(app.selection(0), &[(0, 0)]);
+    }
+
+
+    #[test]
+    fn service_charge_is_added_to_the_grand_total() {
+        let mut app = test_app();
+        app.prefs.service_charge_rate = 0.05;
+        let id = book(&mut app, 0, &[(0, 0), (0, 1)], "Ada Lovelace");
+        let booking = app.bookings.iter().find(|b| b.id == id).unwrap();
+        assert!((booking.service_charge - booking.price * 0.05).abs() < 1e-9);
+        assert!((booking.grand_total - booking.price * 1.05).abs() < 1e-9);
+        // Far enough ahead for a full refund, which includes the charge
+        assert_eq!(app.refund_for(booking), booking.grand_total);
+        app.prefs.service_charge_rate = -0.05;
+        assert_eq!(app.service_charge_for(1000.0), 0.0);
+    }
+
+    #[test]
+    fn zero_service_charge_passes_the_price_through() {
+        let mut app = test_app();
+        assert_eq!(app.prefs.service_charge_rate, 0.0);
+        let id = book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        let booking = app.bookings.iter().find(|b| b.id == id).unwrap();
+        assert_eq!(booking.service_charge, 0.0);
+        assert_eq!(booking.grand_total, booking.price);
+        assert!(!app.total_preview_text(0).contains("Service charge"));
+    }
+
+
+    #[test]
+    fn same_name_bookings_get_distinct_references() {
+        let first = sample_booking("TH-7K9QX2", 0, "John Smith", &["A1"], 1500.0);
+        let second = sample_booking("TH-7K4HMD", 0, "John Smith", &["A2"], 1500.0);
+        assert_eq!(first.name_with_ref(), "John Smith (#9QX2)");
+        assert_eq!(second.name_with_ref(), "John Smith (#4HMD)");
+        assert_eq!(sample_booking("AB", 0, "Ada", &["A1"], 0.0).name_with_ref(), "Ada (#AB)");
+
+        let mut app = test_app();
+        let a = book(&mut app, 0, &[(0, 0)], "John Smith");
+        let label = app.bookings.iter().find(|bk| bk.id == a).unwrap().name_with_ref();
+        assert_eq!(label, format!("John Smith (#{})", &a[a.len() - 4..]));
+    }
+
+
+    #[test]
+    fn just_booked_highlight_does_not_follow_to_another_show() {
+        let mut app = test_app();
+        book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        assert_eq!(app.last_booked, [(0, 0)]);
+        app.handle_message(Message::SelectShow(1));
+        assert!(app.last_booked.is_empty());
+
+        app.prefs.keep_booking_for_show = false;
+        book(&mut app, 1, &[(0, 0)], "Alan Turing");
+        assert_eq!(app.current_view, View::ShowSelection);
+        assert!(app.last_booked.is_empty());
+    }
+
+
+    #[test]
+    fn confirming_a_booking_dismisses_the_leave_prompt() {
+        let mut app = test_app();
+        app.handle_message(Message::SelectShow(0));
+        app.handle_message(Message::SelectSeat(0, 0));
+        app.handle_message(Message::CustomerNameChanged("Ada Lovelace".to_string()));
+        app.handle_message(Message::ChangeView(View::ShowSelection));
+        assert_eq!(app.pending_view_change, Some(View::ShowSelection));
+        app.last_confirm_instant = None;
+        app.handle_message(Message::ConfirmBooking);
+        assert_eq!(app.error_message, None);
+        assert_eq!(app.pending_view_change, None);
+        assert_eq!(app.current_view, View::Booking);
+    }
+
+
+    #[test]
+    fn seat_codes_pick_seats_when_changing_a_booking() {
+        let mut app = test_app();
+        let id = book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        app.handle_message(Message::ChangeView(View::ModifyBooking));
+        app.handle_message(Message::ModifyIdChanged(id.clone()));
+        app.handle_message(Message::LoadBookingForModify);
+        app.handle_message(Message::SeatCodeChanged("b3".to_string()));
+        app.handle_message(Message::SelectSeatByCode);
+        assert_eq!(app.error_message, None);
+        assert_eq!(app.modifying.as_ref().map(|(_, sel)| sel.as_slice()), Some(&[(0, 0), (1, 2)][..]));
+        assert!(app.selection(0).is_empty());
+        let _ = app.modify_booking_view();
+    }
+
+
+    #[test]
+    fn phone_numbers_are_optional_but_checked() {
+        assert!(matches!(validate_phone("+94 77 123 4567"), Ok(Some(_))));
+        assert_eq!(validate_phone("12345"), Err("Phone number must have 10 to 15 digits".to_string()));
+        assert_eq!(validate_phone(""), Ok(None));
+        assert_eq!(validate_phone("   "), Ok(None));
+        assert!(validate_phone("077-123-4567").is_err());
+    }
+
+
+    #[test]
+    fn booked_and_reserved_seats_cannot_be_selected() {
+        let mut app = test_app();
+        book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        app.handle_message(Message::SelectSeat(1, 1));
+        let before = app.selection(0).to_vec();
+
+        app.handle_message(Message::SelectSeat(0, 0));
+        assert!(app.error_message.as_deref().is_some_and(|e| e.contains("A1 is already taken")), "{:?}", app.error_message);
+        assert_eq!(app.selection(0), before);
+
+        let last_row = app.seats[0].len() - 1;
+        assert!(app.seats[0][last_row][0].is_reserved);
+        app.handle_message(Message::SelectSeat(last_row, 0));
+        assert!(app.error_message.as_deref().is_some_and(|e| e.contains("not available for public booking")), "{:?}", app.error_message);
+        assert_eq!(app.selection(0), before);
+    }
+
+
+    #[test]
+    fn price_multiplier_stacks_with_seat_tier() {
+        let mut app = test_app();
+        app.shows[0].price_multiplier = 1.5;
+        // 1500 base x 1.5 show multiplier, then x1.5 for the VIP seat in row A
+        let vip = book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        let mixed = book(&mut app, 0, &[(0, 1), (2, 0)], "Alan Turing");
+        let price = |id: &str| app.bookings.iter().find(|b| b.id == id).unwrap().price;
+        assert_eq!(price(&vip), 3375.0);
+        assert_eq!(price(&mixed), 3375.0 + 2250.0);
+    }
+
+
+    #[test]
+    fn show_dates_parse_and_display() {
+        let mut show = sample_show(0, "Dune: Part Two");
+        show.date = "15-03-2024".to_string();
+        show.time = "18:00".to_string();
+        let expected = NaiveDateTime::parse_from_str("2024-03-15 18:00", "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(show.starts_at(), Some(expected));
+        assert_eq!(show.display_date(DateFormat::Numeric), "15-03-2024");
+        assert_eq!(show.display_date(DateFormat::Long), "Mar 15, 2024");
+
+        let mut bad_time = show.clone();
+        bad_time.time = "6pm".to_string();
+        assert_eq!(bad_time.starts_at(), None);
+
+        show.date = "2024/03/15".to_string();
+        assert_eq!(show.starts_at(), None);
+        assert_eq!(show.display_date(DateFormat::Long), "2024/03/15");
+
+        let mut app = test_app();
+        app.shows[1].date = "32-13-2024".to_string();
+        assert_eq!(app.show_datetime(1), None);
+        assert!(app.show_datetime(0).is_some());
+        assert_eq!(app.show_datetime(99), None);
+    }
+
+
+    #[test]
+    fn confirming_without_seats_is_refused() {
+        let mut app = test_app();
+        app.handle_message(Message::SelectShow(0));
+        app.handle_message(Message::CustomerNameChanged("Ada Lovelace".to_string()));
+        app.handle_message(Message::ConfirmBooking);
+        assert_eq!(app.error_message.as_deref(), Some("Please select at least one seat"));
+        assert!(app.bookings.is_empty());
+        assert_eq!(app.customer_name, "Ada Lovelace");
+    }
+
+
+    #[test]
+    fn customer_history_matches_names_case_insensitively() {
+        let mut app = test_app();
+        let first = book(&mut app, 0, &[(0, 0)], "Ada Lovelace");
+        let second = book(&mut app, 1, &[(2, 0), (2, 1)], "ADA LOVELACE");
+        book(&mut app, 1, &[(3, 1)], "Alan Turing");
+        let found: Vec<&str> = app.customer_bookings(" ada lovelace ").iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(found, [first.as_str(), second.as_str()]);
+        let expected: f64 = app.bookings.iter().filter(|b| b.id == first || b.id == second).map(|b| b.grand_total).sum();
+        assert_eq!(app.customer_total("ada lovelace"), expected);
+    }
+
+
+    #[test]
+    fn selection_stops_at_six_seats() {
+        let mut app = test_app();
+        app.handle_message(Message::SelectShow(0));
+        for col in 0..5 {
+            app.handle_message(Message::SelectSeat(2, col));
+        }
+        app.handle_message(Message::SelectSeat(1, 0));
+        assert_eq!(app.error_message, None);
+        assert_eq!(app.selection(0).len(), 6);
+        app.handle_message(Message::SelectSeat(1, 1));
+        assert_eq!(app.error_message.as_deref(), Some("You can select at most 6 seats"));
+        assert_eq!(app.selection(0).len(), 6);
+        assert!(!app.selection(0).contains(&(1, 1)));
+    }
+
+
+    #[test]
+    fn views_survive_bookings_for_missing_shows() {
+        let mut app = test_app();
+        book(&mut app, 5, &[(0, 0)], "Ada Lovelace");
+        app.bookings.push(sample_booking("TH-GONE99", 99, "Grace Hopper", &["A1"], 1500.0));
+        app.refresh_stats();
+        let _ = app.records_view();
+        let _ = app.statistics_view();
+        app.pending_cancel = Some("TH-GONE99".to_string());
+        let _ = app.cancel_booking_view();
+
+        // Dropping the last show leaves its booking pointing past the end of the lineup
+        app.shows.truncate(5);
+        app.refresh_stats();
+        app.pending_cancel = app.bookings.first().map(|b| b.id.clone());
+        let _ = app.records_view();
+        let _ = app.cancel_booking_view();
+        let _ = app.statistics_view();
+    }
+}